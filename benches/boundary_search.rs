@@ -0,0 +1,112 @@
+//!
+//! ```
+//! cargo bench --bench boundary-search
+//! ```
+//!
+//! The body size can be set by `BENCH_BODY_MB`, defaults to 400MB.
+
+use std::{
+    convert::Infallible,
+    env,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::{BoundarySearcher, FormData, Limits, MemmemSearcher};
+
+const BOUNDARY: &str = "------------------------627436eaefdbc285";
+const CHUNK_SIZE: usize = 512 * 1024;
+const ROUNDS: u32 = 3;
+
+/// Scans byte by byte, as a baseline.
+struct NaiveSearcher;
+
+impl BoundarySearcher for NaiveSearcher {
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+}
+
+/// Body with many near-miss boundary bytes, like `\r\n--------`.
+fn payload(size: usize) -> Vec<u8> {
+    let near_miss = format!("\r\n--{}", &BOUNDARY[..BOUNDARY.len() - 1]);
+    let mut payload = Vec::with_capacity(size + 256);
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"large.bin\"\r\n\r\n").as_bytes());
+    while payload.len() < size {
+        payload.extend_from_slice(near_miss.as_bytes());
+        payload.extend((0..64).map(|i: u8| i.wrapping_mul(31)));
+    }
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    payload
+}
+
+fn parse<S>(rt: &tokio::runtime::Runtime, payload: &Bytes, searcher: S) -> (Duration, u64)
+where
+    S: BoundarySearcher + 'static,
+{
+    let size = payload.len();
+    let chunks = (0..size)
+        .step_by(CHUNK_SIZE)
+        .map(|i| Ok::<_, Infallible>(payload.slice(i..size.min(i + CHUNK_SIZE))))
+        .collect::<Vec<_>>();
+    let limits = Limits::default()
+        .file_size(size)
+        .stream_size(size as u64)
+        .buffer_size(CHUNK_SIZE);
+
+    let now = Instant::now();
+    let bytes = rt.block_on(async {
+        let mut form = FormData::with_limits(stream::iter(chunks), BOUNDARY, limits)
+            .with_searcher(searcher)
+            .unwrap();
+
+        let mut n = 0;
+        while let Some(mut field) = form.try_next().await.unwrap() {
+            while let Some(buf) = field.try_next().await.unwrap() {
+                n += buf.len() as u64;
+            }
+        }
+        n
+    });
+    (now.elapsed(), bytes)
+}
+
+fn report(name: &str, elapsed: Duration, bytes: u64) {
+    let secs = elapsed.as_secs_f64() / f64::from(ROUNDS);
+    #[allow(clippy::cast_precision_loss)]
+    let throughput = bytes as f64 / secs / 1024.0 / 1024.0;
+    println!(
+        "{name:<24} {:>10.3}ms {throughput:>10.1}MB/s",
+        secs * 1000.0
+    );
+}
+
+fn main() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let size = env::var("BENCH_BODY_MB")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(400)
+        * 1024
+        * 1024;
+    let payload = Bytes::from(payload(size));
+
+    let mut elapsed = Duration::ZERO;
+    let mut bytes = 0;
+    for _ in 0..ROUNDS {
+        let (e, n) = parse(&rt, &payload, MemmemSearcher);
+        elapsed += e;
+        bytes = n;
+    }
+    report("MemmemSearcher", elapsed, bytes);
+
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let (e, n) = parse(&rt, &payload, NaiveSearcher);
+        elapsed += e;
+        bytes = n;
+    }
+    report("NaiveSearcher", elapsed, bytes);
+}
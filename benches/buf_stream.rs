@@ -0,0 +1,102 @@
+//!
+//! ```
+//! cargo bench --bench buf-stream
+//! ```
+
+use std::{
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
+use bytes::{Buf, Bytes};
+use futures_util::stream::{self, Stream, TryStreamExt};
+
+use form_data::{BufStream, FormData, Limits};
+
+const BOUNDARY: &str = "------------------------627436eaefdbc285";
+const CHUNK_SIZE: usize = 64 * 1024;
+const PAYLOAD_SIZE: usize = 64 * 1024 * 1024;
+const ROUNDS: u32 = 16;
+
+fn payload(size: usize) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(size + 256);
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"large.bin\"\r\n\r\n").as_bytes());
+    payload.extend((0..size).map(|i| (i % 251) as u8));
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    payload
+}
+
+async fn parse<S>(body: S) -> u64
+where
+    S: Stream<Item = Result<Bytes, Infallible>> + Unpin,
+{
+    let limits = Limits::default()
+        .file_size(PAYLOAD_SIZE)
+        .stream_size(2 * PAYLOAD_SIZE as u64)
+        .buffer_size(CHUNK_SIZE);
+    let mut form = FormData::with_limits(body, BOUNDARY, limits);
+
+    let mut n = 0;
+    while let Some(mut field) = form.try_next().await.unwrap() {
+        while let Some(buf) = field.try_next().await.unwrap() {
+            n += buf.len() as u64;
+        }
+    }
+    n
+}
+
+fn chunks(payload: &Bytes) -> Vec<Result<Bytes, Infallible>> {
+    (0..payload.len())
+        .step_by(CHUNK_SIZE)
+        .map(|i| Ok(payload.slice(i..payload.len().min(i + CHUNK_SIZE))))
+        .collect()
+}
+
+fn report(name: &str, elapsed: Duration, bytes: u64) {
+    let secs = elapsed.as_secs_f64() / f64::from(ROUNDS);
+    #[allow(clippy::cast_precision_loss)]
+    let throughput = bytes as f64 / secs / 1024.0 / 1024.0;
+    println!(
+        "{name:<24} {:>10.3}ms {throughput:>10.1}MB/s",
+        secs * 1000.0
+    );
+}
+
+fn main() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let payload = Bytes::from(payload(PAYLOAD_SIZE));
+
+    // `MyStream` approach of the warp example
+    let mut elapsed = Duration::ZERO;
+    let mut bytes = 0;
+    for _ in 0..ROUNDS {
+        let body = stream::iter(chunks(&payload)).map_ok(|mut b| b.copy_to_bytes(b.remaining()));
+        let now = Instant::now();
+        bytes = rt.block_on(parse(body));
+        elapsed += now.elapsed();
+    }
+    report("copy_to_bytes", elapsed, bytes);
+
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let body = BufStream::new(stream::iter(chunks(&payload)));
+        let now = Instant::now();
+        bytes = rt.block_on(parse(body));
+        elapsed += now.elapsed();
+    }
+    report("BufStream", elapsed, bytes);
+
+    // non-contiguous chunks
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let body = stream::iter(chunks(&payload).into_iter().map(|b| {
+            let mut b = b.unwrap();
+            let half = b.split_off(b.len() / 2);
+            Ok::<_, Infallible>(b.chain(half))
+        }));
+        let now = Instant::now();
+        bytes = rt.block_on(parse(BufStream::new(body)));
+        elapsed += now.elapsed();
+    }
+    report("BufStream (chained)", elapsed, bytes);
+}
@@ -3,7 +3,7 @@
 // use anyhow::Result;
 // use async_fs::File;
 // use bytes::Buf;
-// use form_data::FormData;
+// use form_data::{BufStream, FormData};
 // use futures_util::{
 //     io::{self, AsyncWriteExt},
 //     stream::{Stream, TryStreamExt},
@@ -23,7 +23,7 @@
 //     txt.push_str("\r\n");
 //
 //     let mut form = FormData::new(
-//         body.map_ok(|mut b| b.copy_to_bytes(b.remaining())),
+//         BufStream::new(body),
 //         m.get_param(mime::BOUNDARY).unwrap().as_str(),
 //     );
 //
@@ -2,25 +2,45 @@ use std::{
     error::Error as StdError,
     fs::File,
     io::Write,
+    path::Path,
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll},
 };
 
 use bytes::{Bytes, BytesMut};
+#[cfg(feature = "tokio")]
+use futures_util::stream::FuturesUnordered;
 use futures_util::{
-    io::{self, AsyncRead, AsyncWrite, AsyncWriteExt},
-    stream::{Stream, TryStreamExt},
-};
-use http::{
-    header::{CONTENT_DISPOSITION, CONTENT_TYPE},
-    HeaderValue,
+    future::{poll_fn, BoxFuture},
+    io::{self, AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter},
+    stream::{Stream, StreamExt, TryStreamExt},
 };
+use http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use indexmap::IndexMap;
 use tracing::trace;
 
+#[cfg(feature = "sniff")]
+use crate::sniff;
+#[cfg(feature = "spool")]
+use crate::spool::create_spool_file;
+#[cfg(feature = "tokio")]
+use crate::utils::create_unique_file;
+#[cfg(feature = "prealloc")]
+use crate::utils::{preallocate, shrink_preallocated};
+#[cfg(feature = "base64")]
+use crate::Base64Stream;
+#[cfg(feature = "decrypt")]
+use crate::Decrypt;
+#[cfg(feature = "json")]
+use crate::{graphql::GraphQl, JsonLines};
 use crate::{
-    utils::{parse_content_disposition, parse_content_type, parse_part_headers},
-    Error, Field, Flag, FormData, Result, State,
+    line_ending::Normalizer,
+    utils::{create_temp_file, decode_text, parse_content_type, persist_temp_file},
+    Error, Event, Events, Field, FieldMeta, FormData, Limits, LineEnding, Lines, ReadMode, Result,
+    State, Take,
 };
+#[cfg(feature = "spool")]
+use crate::{FieldData, Form};
 
 impl<T, B, E> Stream for State<T>
 where
@@ -31,28 +51,17 @@ where
     type Item = Result<Bytes>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut processed = 0;
+
         loop {
             if self.is_readable {
                 // part
-                trace!("attempting to decode a part");
-
-                // field
-                if let Some(data) = self.decode() {
-                    trace!("part decoded from buffer");
-                    return Poll::Ready(Some(Ok(data)));
+                if let Poll::Ready(data) = self.poll_buffered() {
+                    return Poll::Ready(data.map(Ok));
                 }
 
-                // field stream is ended
-                if Flag::Next == self.flag {
-                    return Poll::Ready(None);
-                }
-
-                // whole stream is ended
-                if Flag::Eof == self.flag {
-                    self.length -= self.buffer.len() as u64;
-                    self.buffer.clear();
-                    self.eof = true;
-                    return Poll::Ready(None);
+                if let Err(e) = self.check_multipart().and_then(|()| self.check_eof()) {
+                    return Poll::Ready(Some(Err(e)));
                 }
 
                 self.is_readable = false;
@@ -65,31 +74,45 @@ where
                 continue;
             }
 
-            self.buffer.reserve(1);
-            let bytect = match Pin::new(self.io_mut()).poll_next(cx) {
+            // yields to the executor
+            if self.limits.checked_poll_budget(processed).is_some() {
+                trace!(processed, "poll budget is exhausted");
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            // paces the reads
+            #[cfg(feature = "tokio")]
+            ready!(self.poll_rate_limit(cx));
+
+            self.reserve_buffer(1);
+            match Pin::new(self.io_mut()).poll_next(cx) {
                 Poll::Pending => {
                     return Poll::Pending;
                 }
                 Poll::Ready(Some(Ok(b))) => {
-                    let b = b.into();
+                    let b: Bytes = b.into();
                     let l = b.len() as u64;
 
-                    if let Some(max) = self.limits.checked_stream_size(self.length + l) {
-                        return Poll::Ready(Some(Err(Error::PayloadTooLarge(max))));
+                    // an empty chunk is not the end of stream
+                    if l == 0 {
+                        trace!("empty chunk is skipped");
+                        continue;
+                    }
+
+                    if let Err(e) = self.count_stream_size(l) {
+                        return Poll::Ready(Some(Err(e)));
                     }
 
-                    self.buffer.extend_from_slice(&b);
-                    self.length += l;
-                    l
+                    self.extend_buffer(&b);
+                    processed += b.len();
                 }
                 Poll::Ready(Some(Err(e))) => {
                     return Poll::Ready(Some(Err(Error::BoxError(e.into()))))
                 }
-                Poll::Ready(None) => 0,
-            };
-
-            if bytect == 0 {
-                self.eof = true;
+                Poll::Ready(None) => {
+                    self.eof = true;
+                }
             }
 
             self.is_readable = true;
@@ -97,6 +120,43 @@ where
     }
 }
 
+impl<T, B, E> State<T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    /// Drains the rest data of the active field into buffer, see `ReadMode::Buffered`.
+    fn poll_drain(&mut self, cx: &mut Context<'_>, is_file: bool) -> Poll<Result<()>> {
+        while let Some(buf) = ready!(Pin::new(&mut *self).poll_next(cx)?) {
+            self.drain(is_file, &buf)?;
+        }
+
+        trace!(index = self.total - 1, "drained");
+        self.drained();
+        if let Some(waker) = self.waker_mut().take() {
+            waker.wake();
+        }
+
+        Poll::Ready(self.take_part_error())
+    }
+
+    /// Skips the rest data of the active field, the size limits are still applied.
+    fn poll_skip(&mut self, cx: &mut Context<'_>, is_file: bool) -> Poll<Result<()>> {
+        while ready!(Pin::new(&mut *self).poll_next(cx)?).is_some() {
+            self.check_part_size(is_file, self.part_size())?;
+        }
+
+        trace!(index = self.total - 1, "skipped");
+        self.active = None;
+        if let Some(waker) = self.waker_mut().take() {
+            waker.wake();
+        }
+
+        Poll::Ready(self.take_part_error())
+    }
+}
+
 impl<T, B, E> Field<T>
 where
     T: Stream<Item = Result<B, E>> + Unpin,
@@ -112,6 +172,18 @@ where
         Ok(bytes.freeze())
     }
 
+    /// Reads field data to a UTF-8 string, see `Limits::strip_bom`.
+    pub async fn text(&mut self) -> Result<String> {
+        let strip_bom = self.limits().is_ok_and(|limits| limits.strip_bom);
+        decode_text(self.bytes().await?, strip_bom)
+    }
+
+    /// Reads field data to bytes, then returns them with the metadata.
+    pub async fn consume(mut self) -> Result<(FieldMeta, Bytes)> {
+        let bytes = self.bytes().await?;
+        Ok((self.meta(), bytes))
+    }
+
     /// Copys large buffer to `AsyncRead`, hyper can support large buffer,
     /// 8KB <= buffer <= 512KB, so if we want to handle large buffer.
     /// `Form::set_max_buf_size(512 * 1024);`
@@ -119,6 +191,37 @@ where
     pub async fn copy_to<W>(&mut self, writer: &mut W) -> Result<u64>
     where
         W: AsyncWrite + Send + Unpin + 'static,
+    {
+        self.write_all_to(writer).await
+    }
+
+    /// Copys field data to `AsyncWrite`, picks the path by `Limits::buffer_size`.
+    ///
+    /// If the buffer size is larger than `Limits::DEFAULT_BUFFER_SIZE`, such as set by
+    /// `FormData::set_max_buf_size`, each large chunk is written whole like `copy_to`.
+    /// Otherwise the small chunks are coalesced into 8KB writes.
+    pub async fn copy_auto<W>(&mut self, writer: &mut W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let buffer_size = self
+            .limits()
+            .map_or(Limits::DEFAULT_BUFFER_SIZE, |limits| limits.buffer_size);
+
+        if buffer_size > Limits::DEFAULT_BUFFER_SIZE {
+            trace!(buffer_size, "copying large chunks");
+            return self.write_all_to(writer).await;
+        }
+
+        trace!(buffer_size, "copying buffered chunks");
+        let mut writer = BufWriter::with_capacity(Limits::DEFAULT_BUFFER_SIZE, writer);
+        self.write_all_to(&mut writer).await
+    }
+
+    /// Writes each chunk of field data to `writer`, then flushes it.
+    async fn write_all_to<W>(&mut self, writer: &mut W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
     {
         let mut n = 0;
         while let Some(buf) = self.try_next().await? {
@@ -142,13 +245,349 @@ where
         Ok(n as u64)
     }
 
+    /// Copys text to a File, normalizes the line endings as it streams.
+    ///
+    /// Binary files should use the passthrough methods, like `copy_to_file`.
+    pub async fn copy_to_file_normalized(
+        &mut self,
+        file: &mut File,
+        mode: LineEnding,
+    ) -> Result<u64> {
+        let mut normalizer = Normalizer::new(mode);
+        let mut out = Vec::new();
+        let mut n = 0;
+        while let Some(buf) = self.try_next().await? {
+            normalizer.normalize(&buf, &mut out);
+            file.write_all(&out)?;
+            n += out.len();
+            out.clear();
+        }
+        normalizer.finish(&mut out);
+        file.write_all(&out)?;
+        n += out.len();
+        file.flush()?;
+        Ok(n as u64)
+    }
+
+    /// Uploads field data in parts of exactly `part_size` bytes, except the last one,
+    /// like the multipart upload of object stores, returns the size of uploaded data.
+    ///
+    /// `f` is invoked with the part index and data in order, nothing is uploaded if the
+    /// field is empty.
+    ///
+    /// # Panics
+    ///
+    /// If `part_size` is zero.
+    pub async fn chunked_upload<'a, F>(&mut self, part_size: usize, mut f: F) -> Result<u64>
+    where
+        F: FnMut(usize, Bytes) -> BoxFuture<'a, Result<()>>,
+    {
+        assert!(part_size > 0, "part size must be greater than zero");
+
+        let mut part = BytesMut::new();
+        let mut index = 0;
+        let mut n = 0;
+
+        while let Some(mut buf) = self.try_next().await? {
+            n += buf.len();
+
+            while !buf.is_empty() {
+                // uploads the whole part of chunk without copying
+                if part.is_empty() && buf.len() >= part_size {
+                    f(index, buf.split_to(part_size)).await?;
+                    index += 1;
+                    continue;
+                }
+
+                let l = buf.len().min(part_size - part.len());
+                part.extend_from_slice(&buf.split_to(l));
+
+                if part.len() == part_size {
+                    f(index, part.split().freeze()).await?;
+                    index += 1;
+                }
+            }
+        }
+
+        if !part.is_empty() {
+            f(index, part.freeze()).await?;
+        }
+
+        Ok(n as u64)
+    }
+
+    /// Copys field data to `tokio::io::AsyncWrite`, such as `tokio::fs::File`,
+    /// without the compat shim of `tokio_util`.
+    #[cfg(feature = "tokio")]
+    pub async fn copy_to_tokio<W>(&mut self, writer: &mut W) -> Result<u64>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let mut n = 0;
+        while let Some(buf) = self.try_next().await? {
+            writer.write_all(&buf).await?;
+            n += buf.len();
+        }
+        writer.flush().await?;
+        Ok(n as u64)
+    }
+
+    /// Sends field data to a channel, awaits the capacity of channel for backpressure.
+    ///
+    /// Stops when the receiver is dropped, returns the size of sent data.
+    #[cfg(feature = "tokio")]
+    pub async fn send_to(&mut self, tx: tokio::sync::mpsc::Sender<Bytes>) -> Result<u64> {
+        let mut n = 0;
+        while let Some(buf) = self.try_next().await? {
+            let l = buf.len();
+            if tx.send(buf).await.is_err() {
+                trace!("receiver is dropped");
+                break;
+            }
+            n += l;
+        }
+        Ok(n as u64)
+    }
+
+    /// Copys bytes to a File, preallocates `hint` bytes before, such as the declared size.
+    ///
+    /// Reduces fragmentation and surfaces `ENOSPC` early,
+    /// the unwritten space is shrunk if field data is shorter than `hint`.
+    #[cfg(feature = "prealloc")]
+    pub async fn copy_to_file_prealloc(&mut self, file: &mut File, hint: u64) -> Result<u64> {
+        let len = preallocate(file, hint)?;
+        let n = self.copy_to_file(file).await?;
+        shrink_preallocated(file, len)?;
+        Ok(n)
+    }
+
+    /// Reads field data into memory, or spools it to a temp file if it is over
+    /// `Limits::spool_threshold`.
+    #[cfg(feature = "spool")]
+    pub async fn spool(&mut self) -> Result<FieldData> {
+        let (threshold, dir) = self.spool_options()?;
+        let mut buffer = BytesMut::new();
+
+        while let Some(buf) = self.try_next().await? {
+            if threshold.is_some_and(|max| buffer.len() + buf.len() > max) {
+                let mut file = create_spool_file(dir.as_deref())?;
+                file.write_all(&buffer)?;
+                file.write_all(&buf)?;
+                while let Some(buf) = self.try_next().await? {
+                    file.write_all(&buf)?;
+                }
+                file.flush()?;
+                return Ok(FieldData::File(file));
+            }
+            buffer.extend_from_slice(&buf);
+        }
+
+        Ok(FieldData::Memory(buffer.freeze()))
+    }
+
+    /// Saves field data to a file atomically.
+    ///
+    /// Writes to a sibling temp file, then renames it to `path` once field data is fully read,
+    /// the temp file is removed on error, so no partial file is left at `path`.
+    pub async fn save_atomic<P>(&mut self, path: P) -> Result<u64>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let (temp, mut file) = create_temp_file(path)?;
+        let res = self.copy_to_file(&mut file).await;
+        persist_temp_file(&temp, path, file, res)
+    }
+
     /// Ignores current field data, pass it.
+    ///
+    /// Field is still `fully_read` if no data was left to discard.
     pub async fn ignore(&mut self) -> Result<()> {
+        let mut discarded = false;
         while let Some(buf) = self.try_next().await? {
-            drop(buf);
+            discarded |= !buf.is_empty();
+        }
+        if discarded {
+            self.fully_read = false;
         }
         Ok(())
     }
+
+    /// Skips the rest field data, returns `true` if any bytes were skipped,
+    /// `false` if the field was already fully consumed.
+    ///
+    /// The rest data is discarded by the state, see `Take::drain`,
+    /// it is not counted in `length`.
+    pub async fn skip_to_end(&mut self) -> Result<bool> {
+        let mut skipped = self.peeked.take().is_some_and(|buf| !buf.is_empty());
+
+        if let Some(state) = self.state.clone() {
+            skipped |= poll_fn(|cx| {
+                let mut state = state.try_lock().map_err(Error::from)?;
+
+                // the rest data is drained, see `ReadMode::Buffered`
+                if let Some(rest) = state.buffered.remove(&self.index) {
+                    return Poll::Ready(Ok(!rest.is_empty()));
+                }
+
+                match state.active {
+                    Some(is_file) => {
+                        ready!(state.poll_skip(cx, is_file))?;
+                        Poll::Ready(Ok(state.part_size() > self.length))
+                    }
+                    None => Poll::Ready(Ok(false)),
+                }
+            })
+            .await
+            .map_err(|e| self.map_error(e))?;
+
+            trace!(length = self.length, "field is skipped");
+            drop(self.state.take());
+        }
+
+        if skipped {
+            self.fully_read = false;
+        }
+        Ok(skipped)
+    }
+
+    /// Reads the field data which is already buffered, never polls the underlying stream.
+    ///
+    /// Returns `None` if no data is ready or the field is ended.
+    pub fn try_read_buffered(&mut self) -> Result<Option<Bytes>> {
+        if let Some(buf) = self.peeked.take() {
+            return Ok(Some(buf));
+        }
+
+        match self.poll_state_with(|state| state.poll_buffered().map(|data| data.map(Ok))) {
+            Poll::Ready(Some(res)) => res.map(Some).map_err(|e| self.map_error(e)),
+            Poll::Ready(None) | Poll::Pending => Ok(None),
+        }
+    }
+
+    /// Maps each chunk of field data as it streams,
+    /// the limits still apply to the original data.
+    pub fn map_chunks<'a, F>(&'a mut self, mut f: F) -> impl Stream<Item = Result<Bytes>> + 'a
+    where
+        F: FnMut(Bytes) -> Result<Bytes> + 'a,
+    {
+        self.map(move |res| res.and_then(&mut f))
+    }
+
+    /// Peeks at most `n` bytes of field data, they will be yielded again when reading.
+    pub async fn peek(&mut self, n: usize) -> Result<Bytes> {
+        let mut peeked = self
+            .peeked
+            .take()
+            .map(|b| BytesMut::from(&b[..]))
+            .unwrap_or_default();
+
+        while peeked.len() < n {
+            match poll_fn(|cx| self.poll_state(cx)).await {
+                Some(buf) => peeked.extend_from_slice(&buf?),
+                None => break,
+            }
+        }
+
+        let peeked = peeked.freeze();
+        let bytes = peeked.slice(..n.min(peeked.len()));
+
+        if !peeked.is_empty() {
+            self.peeked.replace(peeked);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Verifies the declared `content_type` by sniffing the magic number of field data.
+    ///
+    /// Field data is peeked, so it is still fully readable after verification.
+    #[cfg(feature = "sniff")]
+    pub async fn verify_content_type(&mut self) -> Result<bool> {
+        let bytes = self.peek(sniff::MAX_MAGIC_LEN).await?;
+        Ok(sniff::verify(self.content_type.as_ref(), &bytes))
+    }
+
+    fn poll_state(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
+        self.poll_state_with(|state| Pin::new(state).poll_next(cx))
+    }
+
+    /// Polls the field data from `f`, unless the rest data is drained.
+    fn poll_state_with<F>(&mut self, f: F) -> Poll<Option<Result<Bytes>>>
+    where
+        F: FnOnce(&mut State<T>) -> Poll<Option<Result<Bytes>>>,
+    {
+        let span = self.span.clone();
+        let _enter = span.enter();
+        trace!(consumed = self.state.is_none(), "polling field");
+
+        let Some(state) = self.state.clone() else {
+            return Poll::Ready(None);
+        };
+
+        let mut state = state.try_lock().map_err(Error::from)?;
+
+        let polled = match state.buffered.get_mut(&self.index) {
+            // the rest data is drained, see `ReadMode::Buffered`
+            Some(rest) => Poll::Ready((!rest.is_empty()).then(|| Ok(rest.split().freeze()))),
+            None => f(&mut state),
+        };
+
+        match polled? {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(res) => match res {
+                None => {
+                    if state.buffered.remove(&self.index).is_none() {
+                        state.active = None;
+                        if let Some(waker) = state.waker_mut().take() {
+                            waker.wake();
+                        }
+                    }
+                    trace!(length = self.length, "field is ended");
+
+                    // the part is ended, the field can't be read any further
+                    if let Err(e) = self.check_ended(&mut state) {
+                        let e = state.record_error(e);
+                        drop(self.state.take());
+                        return Poll::Ready(Some(Err(e)));
+                    }
+
+                    drop(self.state.take());
+                    self.fully_read = true;
+
+                    if let Some(hook) = state.on_field_complete.as_mut() {
+                        hook(&self.meta(), self.length as u64);
+                    }
+
+                    Poll::Ready(None)
+                }
+                Some(buf) => {
+                    let l = buf.len();
+
+                    if let Err(e) = self.check_size(&state.limits, l) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+
+                    #[cfg(feature = "json")]
+                    if let Some(graphql) = state.graphql.as_mut().filter(|_| self.is_graphql_map())
+                    {
+                        graphql.extend_map(&buf);
+                    }
+
+                    #[cfg(feature = "checksum")]
+                    if let Some(hasher) = self.hasher.as_mut() {
+                        hasher.update(&buf);
+                    }
+
+                    self.length = self.length.saturating_add(l);
+                    trace!(bytes = l, length = self.length, "polled bytes");
+                    Poll::Ready(Some(Ok(buf)))
+                }
+            },
+        }
+    }
 }
 
 /// Reads payload data from part, then puts them to anywhere
@@ -167,7 +606,7 @@ where
             Poll::Pending => Poll::Pending,
             Poll::Ready(None) => Poll::Ready(Ok(0)),
             Poll::Ready(Some(Ok(b))) => Poll::Ready(Ok(buf.write(&b)?)),
-            Poll::Ready(Some(Err(e))) => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Err(io::Error::other(e))),
         }
     }
 }
@@ -182,76 +621,225 @@ where
     type Item = Result<Bytes>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        trace!("polling {} {}", self.index, self.state.is_some());
-
-        let Some(state) = self.state.clone() else {
-            return Poll::Ready(None);
-        };
-
-        let is_file = self.filename.is_some();
-        let mut state = state
-            .try_lock()
-            .map_err(|e| Error::TryLockError(e.to_string()))?;
-
-        match Pin::new(&mut *state).poll_next(cx)? {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(res) => match res {
-                None => {
-                    if let Some(waker) = state.waker_mut().take() {
-                        waker.wake();
-                    }
-                    trace!("polled {}", self.index);
-                    drop(self.state.take());
-                    Poll::Ready(None)
-                }
-                Some(buf) => {
-                    let l = buf.len();
-
-                    if is_file {
-                        if let Some(max) = state.limits.checked_file_size(self.length + l) {
-                            return Poll::Ready(Some(Err(Error::FileTooLarge(max))));
-                        }
-                    } else if let Some(max) = state.limits.checked_field_size(self.length + l) {
-                        return Poll::Ready(Some(Err(Error::FieldTooLarge(max))));
-                    }
+        // yields the peeked bytes first
+        if let Some(buf) = self.peeked.take() {
+            return Poll::Ready(Some(Ok(buf)));
+        }
 
-                    self.length += l;
-                    trace!("polled bytes {}/{}", buf.len(), self.length);
-                    Poll::Ready(Some(Ok(buf)))
-                }
-            },
+        match self.poll_state(cx) {
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(self.map_error(e)))),
+            res => res,
         }
     }
 }
 
-/// Reads form-data from request payload body, then yields `Field`
-impl<T, B, E> Stream for FormData<T>
+impl<T, B, E> FormData<T>
 where
     T: Stream<Item = Result<B, E>> + Unpin,
     B: Into<Bytes>,
     E: Into<Box<dyn StdError + Send + Sync>>,
 {
-    type Item = Result<Field<T>>;
+    /// Reads the next field, fails with `Error::Timeout` if its headers don't arrive within
+    /// `timeout`.
+    ///
+    /// The received data is kept, so the parsing can be resumed by calling it again.
+    #[cfg(feature = "tokio")]
+    pub async fn try_next_timeout(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> Result<Option<Field<T>>> {
+        tokio::time::timeout(timeout, self.try_next())
+            .await
+            .map_err(|_| self.map_error(Error::Timeout))?
+    }
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let mut state = self
-            .state
-            .try_lock()
-            .map_err(|e| Error::TryLockError(e.to_string()))?;
+    /// Reads all fields, the large field data is spooled to temp files,
+    /// see `Field::spool`.
+    #[cfg(feature = "spool")]
+    pub async fn buffered(&mut self) -> Result<Vec<(FieldMeta, FieldData)>> {
+        let mut fields = Vec::new();
+        while let Some(mut field) = self.try_next().await? {
+            let data = field.spool().await?;
+            fields.push((field.meta(), data));
+        }
+        Ok(fields)
+    }
+
+    /// Reads all text fields into a map, the values with the same name are grouped in order.
+    ///
+    /// Fails with `Error::UnexpectedFile` on file parts, unless `Limits::allow_files_ignored`.
+    pub async fn collect_text_map(&mut self) -> Result<IndexMap<String, Vec<String>>> {
+        let limits = self.limits()?;
+        let mut map = IndexMap::<String, Vec<String>>::new();
+
+        while let Some(mut field) = self.try_next().await? {
+            if field.filename.is_some() {
+                if !limits.allow_files_ignored {
+                    return Err(self.map_error(Error::UnexpectedFile(field.name)));
+                }
+                field.ignore().await?;
+                continue;
+            }
+
+            let count = map.get(&field.name).map_or(0, Vec::len) + 1;
+            if let Some(max) = limits.checked_values_per_name(count) {
+                return Err(self.map_error(Error::ValuesTooMany {
+                    name: field.name,
+                    max,
+                }));
+            }
+
+            let value = field.text().await?;
+            map.entry(field.name).or_default().push(value);
+        }
+
+        Ok(map)
+    }
+
+    /// Reads the entire form, the large files are spooled to temp files,
+    /// see `Field::spool`.
+    #[cfg(feature = "spool")]
+    pub async fn collect(mut self) -> Result<Form> {
+        let mut form = Form::default();
+        while let Some(mut field) = self.try_next().await? {
+            if field.filename.is_some() {
+                let data = field.spool().await?;
+                form.push_file(field.meta(), data);
+            } else {
+                let value = field.text().await?;
+                form.push_text(field.name, value);
+            }
+        }
+        Ok(form)
+    }
+
+    /// Reads all fields, writes the files into `dir`, at most `concurrency` writes in parallel.
+    ///
+    /// The file data is buffered in memory to be written concurrently, the larger one than
+    /// `Limits::spool_threshold` is written as it streams. The text fields are skipped.
+    ///
+    /// The files are written by `tokio::fs`, the existing files are never overwritten,
+    /// the file names are suffixed instead, like `name-1.ext`. If it fails, the pending
+    /// writes are aborted and the created files are removed.
+    #[cfg(feature = "tokio")]
+    pub async fn collect_files_to_concurrent<P>(
+        &mut self,
+        dir: P,
+        concurrency: usize,
+    ) -> Result<Vec<(FieldMeta, std::path::PathBuf)>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut created = Vec::new();
+        let mut writes = FuturesUnordered::new();
+
+        let res = self
+            .write_files_to(dir.as_ref(), concurrency.max(1), &mut created, &mut writes)
+            .await;
+
+        if res.is_err() {
+            writes.iter().for_each(tokio::task::JoinHandle::abort);
+            while writes.next().await.is_some() {}
+            for path in created {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        }
+
+        res
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn write_files_to(
+        &mut self,
+        dir: &Path,
+        concurrency: usize,
+        created: &mut Vec<std::path::PathBuf>,
+        writes: &mut FuturesUnordered<tokio::task::JoinHandle<io::Result<()>>>,
+    ) -> Result<Vec<(FieldMeta, std::path::PathBuf)>> {
+        use tokio::io::AsyncWriteExt;
+
+        let threshold = self.limits()?.spool_threshold;
+        let mut files = Vec::new();
+
+        while let Some(mut field) = self.try_next().await? {
+            let Some(filename) = field.filename.as_deref() else {
+                field.ignore().await?;
+                continue;
+            };
+
+            // keeps the file name only, prevents path traversal
+            let name = Path::new(filename)
+                .file_name()
+                .map_or_else(|| field.index.to_string().into(), ToOwned::to_owned);
+
+            let spilled = match threshold {
+                Some(max) => field.peek(max + 1).await?.len() > max,
+                None => false,
+            };
+
+            let (path, mut file) = create_unique_file(dir, &name).await?;
+            created.push(path.clone());
+
+            if spilled {
+                field.copy_to_tokio(&mut file).await?;
+            } else {
+                let bytes = field.bytes().await?;
+                while writes.len() >= concurrency {
+                    if let Some(res) = writes.next().await {
+                        res.map_err(io::Error::other)??;
+                    }
+                }
+                writes.push(tokio::spawn(async move {
+                    file.write_all(&bytes).await?;
+                    file.flush().await
+                }));
+            }
 
-        if state.waker().is_some() {
-            return Poll::Pending;
+            files.push((field.meta(), path));
         }
 
+        while let Some(res) = writes.next().await {
+            res.map_err(io::Error::other)??;
+        }
+
+        Ok(files)
+    }
+
+    fn poll_field(&self, cx: &mut Context<'_>) -> Poll<Option<Result<Field<T>>>> {
+        let mut state = self.state.try_lock().map_err(Error::from)?;
+
+        if let Some(is_file) = state.active {
+            match state.read_mode {
+                ReadMode::Sequential => return Poll::Pending,
+                ReadMode::Strict => return Poll::Ready(Some(Err(Error::FieldStillActive))),
+                ReadMode::Buffered => ready!(state.poll_drain(cx, is_file))?,
+            }
+        }
+
+        // the previous part is suspicious
+        state.take_part_error()?;
+
         match Pin::new(&mut *state).poll_next(cx)? {
             Poll::Pending => Poll::Pending,
             Poll::Ready(res) => match res {
                 None => {
-                    trace!("parse eof");
+                    trace!(total = state.total, length = state.length, "parse eof");
+
+                    // too few parts
+                    if let Some(min) = state.limits.checked_min_parts(state.total) {
+                        return Poll::Ready(Some(Err(Error::TooFewParts { min })));
+                    }
+
+                    // files referenced by graphql map are missing
+                    #[cfg(feature = "json")]
+                    if let Some(Err(e)) = state.graphql.as_ref().map(GraphQl::check) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+
                     Poll::Ready(None)
                 }
                 Some(buf) => {
-                    trace!("parse part");
+                    trace!(header_size = buf.len(), "parse part");
 
                     // too many parts
                     if let Some(max) = state.limits.checked_parts(state.total + 1) {
@@ -259,24 +847,21 @@ where
                     }
 
                     // invalid part header
-                    let Ok(mut headers) = parse_part_headers(&buf) else {
-                        return Poll::Ready(Some(Err(Error::InvalidHeader)));
+                    let mut headers = match state.parse_part_headers(&buf) {
+                        Ok(headers) => headers,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
                     };
 
                     // invalid content disposition
-                    let Some((name, filename)) = headers
-                        .remove(CONTENT_DISPOSITION)
-                        .as_ref()
-                        .map(HeaderValue::as_bytes)
-                        .map(parse_content_disposition)
-                        .and_then(Result::ok)
-                    else {
-                        return Poll::Ready(Some(Err(Error::InvalidContentDisposition)));
-                    };
+                    let disposition = headers.remove(CONTENT_DISPOSITION);
+                    let (name, filename) =
+                        match state.parse_content_disposition(disposition.as_ref()) {
+                            Ok(parsed) => parsed,
+                            Err(e) => return Poll::Ready(Some(Err(e))),
+                        };
 
-                    // field name is too long
-                    if let Some(max) = state.limits.checked_field_name_size(name.len()) {
-                        return Poll::Ready(Some(Err(Error::FieldNameTooLong(max))));
+                    if let Err(e) = state.check_field_name(&name) {
+                        return Poll::Ready(Some(Err(e)));
                     }
 
                     if filename.is_some() {
@@ -284,6 +869,20 @@ where
                         if let Some(max) = state.limits.checked_files(state.files + 1) {
                             return Poll::Ready(Some(Err(Error::FilesTooMany(max))));
                         }
+                        // content type is not allowed
+                        let content_type = parse_content_type(headers.get(CONTENT_TYPE))
+                            .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+                        if state
+                            .limits
+                            .checked_content_type(&name, &content_type)
+                            .is_some()
+                        {
+                            return Poll::Ready(Some(Err(Error::ContentTypeNotAllowed {
+                                name,
+                                content_type,
+                            })));
+                        }
+
                         state.files += 1;
                     } else {
                         // fields too many
@@ -297,9 +896,28 @@ where
                     let mut field = Field::empty();
 
                     field.name = name;
+                    field.raw_content_disposition = disposition;
                     field.filename = filename;
                     field.index = state.index();
-                    field.content_type = parse_content_type(headers.remove(CONTENT_TYPE).as_ref());
+                    field.body_start = Some(state.part_started(&headers, buf.len()));
+                    state.active = Some(field.filename.is_some());
+                    field.header_size = buf.len();
+                    field.content_type = parse_content_type(headers.get(CONTENT_TYPE));
+                    if !state.byteranges {
+                        headers.remove(CONTENT_TYPE);
+                    }
+
+                    #[cfg(feature = "json")]
+                    if let Some(graphql) = state.graphql.as_mut() {
+                        if field.filename.is_some() {
+                            if let Err(e) = graphql.file(&field.name) {
+                                return Poll::Ready(Some(Err(e)));
+                            }
+                        } else if field.content_type.is_none() {
+                            field.content_type = GraphQl::content_type(&field.name);
+                        }
+                    }
+
                     field.state_mut().replace(self.state());
 
                     if !headers.is_empty() {
@@ -309,9 +927,238 @@ where
                     // clone waker, if field is polled data, wake it.
                     state.waker_mut().replace(cx.waker().clone());
 
+                    field.init_span();
+
                     Poll::Ready(Some(Ok(field)))
                 }
             },
         }
     }
 }
+
+/// Reads form-data from request payload body, then yields `Field`
+impl<T, B, E> Stream for FormData<T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Field<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.poll_field(cx) {
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(self.map_error(e)))),
+            res => res,
+        }
+    }
+}
+
+/// Reads form-data from request payload body, then yields `Event`
+impl<T, B, E> Stream for Events<T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Some(field) = this.field.as_mut() {
+            return match Pin::new(field).poll_next(cx)? {
+                Poll::Pending => Poll::Pending,
+                Poll::Ready(Some(buf)) => Poll::Ready(Some(Ok(Event::Data(buf)))),
+                Poll::Ready(None) => {
+                    let length = this.field.take().map_or(0, |field| field.length);
+                    Poll::Ready(Some(Ok(Event::PartEnd { length })))
+                }
+            };
+        }
+
+        if this.completed {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut this.form).poll_next(cx)? {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(field)) => {
+                let meta = field.meta();
+                this.field.replace(field);
+                Poll::Ready(Some(Ok(Event::PartStart(Box::new(meta)))))
+            }
+            Poll::Ready(None) => {
+                this.completed = true;
+                Poll::Ready(Some(Ok(Event::Complete)))
+            }
+        }
+    }
+}
+
+impl<T, B, E> Take<T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    /// Reads the rest of body, the data of unread fields is discarded.
+    ///
+    /// The limits are still applied, such as `Limits::file_size` and `Limits::stream_size`.
+    pub async fn drain(mut self) -> Result<()> {
+        poll_fn(|cx| {
+            let mut state = self.form.state.try_lock().map_err(Error::from)?;
+            match state.active {
+                Some(is_file) => state.poll_skip(cx, is_file),
+                None => Poll::Ready(Ok(())),
+            }
+        })
+        .await
+        .map_err(|e| self.form.map_error(e))?;
+
+        while let Some(mut field) = self.form.try_next().await? {
+            field.ignore().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads form-data from request payload body, then yields at most `n` `Field`
+impl<T, B, E> Stream for Take<T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Field<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.remaining == 0 {
+            return Poll::Ready(None);
+        }
+
+        let res = ready!(Pin::new(&mut self.form).poll_next(cx));
+        if let Some(Ok(_)) = res {
+            self.remaining -= 1;
+        }
+        Poll::Ready(res)
+    }
+}
+
+/// Reads field data, then yields lines
+impl<T, B, E> Stream for Lines<'_, T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(line) = self.split_line() {
+                return Poll::Ready(Some(Ok(line)));
+            }
+
+            if self.eof {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut *self.field).poll_next(cx)? {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(buf)) => self.buffer.extend_from_slice(&buf),
+                Poll::Ready(None) => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields base64 encoded chunks
+#[cfg(feature = "base64")]
+impl<T, B, E> Stream for Base64Stream<'_, T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.eof {
+                return Poll::Ready(self.finish().map(Ok));
+            }
+
+            match Pin::new(&mut *self.field).poll_next(cx)? {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(buf)) => {
+                    if let Some(chunk) = self.encode(&buf) {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+                }
+                Poll::Ready(None) => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields decrypted chunks
+#[cfg(feature = "decrypt")]
+impl<T, B, E> Stream for Decrypt<'_, T>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.eof {
+                return Poll::Ready(self.finish());
+            }
+
+            match Pin::new(&mut *self.field).poll_next(cx)? {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(buf)) => {
+                    if let Some(chunk) = self.update(&buf) {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+                }
+                Poll::Ready(None) => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields JSON values
+#[cfg(feature = "json")]
+impl<T, B, E, D> Stream for JsonLines<'_, T, D>
+where
+    T: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+    D: serde::de::DeserializeOwned,
+{
+    type Item = Result<D>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.failed {
+                return Poll::Ready(None);
+            }
+
+            let Some(line) = std::task::ready!(Pin::new(&mut self.lines).poll_next(cx)?) else {
+                return Poll::Ready(None);
+            };
+
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let res = serde_json::from_slice(&line).map_err(Error::Json);
+            self.failed = res.is_err() && self.fail_fast;
+            return Poll::Ready(Some(res));
+        }
+    }
+}
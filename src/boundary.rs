@@ -0,0 +1,48 @@
+/// Finds the `boundary` parameter of a `multipart/*` content type, without parsing it as mime.
+///
+/// The surrounding quotes of the value are stripped, same as [`mime_boundary`].
+#[must_use]
+pub fn find_boundary(content_type: &[u8]) -> Option<&[u8]> {
+    let mut params = content_type.split(|b| *b == b';');
+
+    let essence = params.next()?.trim_ascii();
+    if !essence
+        .get(..10)
+        .is_some_and(|t| t.eq_ignore_ascii_case(b"multipart/"))
+    {
+        return None;
+    }
+
+    params
+        .find_map(|param| {
+            let n = param.iter().position(|b| *b == b'=')?;
+            param[..n]
+                .trim_ascii()
+                .eq_ignore_ascii_case(mime::BOUNDARY.as_str().as_bytes())
+                .then(|| unquote(param[n + 1..].trim_ascii()))
+        })
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Gets the `boundary` parameter of a `multipart/*` mime.
+///
+/// The surrounding quotes of the value are stripped, same as [`find_boundary`].
+#[must_use]
+pub fn mime_boundary(mime: &mime::Mime) -> Option<&str> {
+    if mime.type_() != mime::MULTIPART {
+        return None;
+    }
+
+    mime.get_param(mime::BOUNDARY)
+        .map(|boundary| unquote(boundary.as_str().as_bytes()))
+        .filter(|boundary| !boundary.is_empty())
+        .and_then(|boundary| std::str::from_utf8(boundary).ok())
+}
+
+/// Strips the surrounding quotes.
+fn unquote(value: &[u8]) -> &[u8] {
+    value
+        .strip_prefix(b"\"")
+        .and_then(|v| v.strip_suffix(b"\""))
+        .unwrap_or(value)
+}
@@ -0,0 +1,60 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_util::stream::Stream;
+
+/// Adapts a stream of [`Buf`] into a stream of [`Bytes`] for [`FormData`].
+///
+/// A contiguous chunk is taken by [`Buf::copy_to_bytes`], which is zero-copy for
+/// `Bytes` and `BytesMut`; a non-contiguous chunk is gathered into one buffer.
+///
+/// [`FormData`]: crate::FormData
+#[derive(Debug)]
+pub struct BufStream<S> {
+    inner: S,
+}
+
+impl<S> BufStream<S> {
+    /// Creates new `BufStream`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Gets the inner stream.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, B, E> Stream for BufStream<S>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: Buf,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner)
+            .poll_next(cx)
+            .map(|res| res.map(|res| res.map(into_bytes)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+fn into_bytes<B: Buf>(mut buf: B) -> Bytes {
+    let n = buf.remaining();
+
+    if buf.chunk().len() == n {
+        return buf.copy_to_bytes(n);
+    }
+
+    let mut bytes = BytesMut::with_capacity(n);
+    bytes.put(buf);
+    bytes.freeze()
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::HeaderValue;
+
+use crate::{
+    utils::{CRLF, DASHES},
+    Error, Result,
+};
+
+const ALPHANUMERIC: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const TSPECIALS: &[u8; 16] = b" ()<>@,;:\\\"/[]?=";
+
+/// Builder, encodes parts into a `multipart/form-data` body.
+#[derive(Debug, Clone)]
+pub struct Builder {
+    boundary: String,
+    parts: Vec<(String, Bytes)>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /// The boundary prefix of common clients, like `curl`.
+    pub const DEFAULT_BOUNDARY_PREFIX: &'static str = "------------------------";
+
+    /// The boundary prefix of `WebKit` based browsers.
+    pub const WEBKIT_BOUNDARY_PREFIX: &'static str = "----WebKitFormBoundary";
+
+    /// Creates new `Builder` with a random boundary, like `------------------------d74496d66958873e`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_boundary(format!(
+            "{}{:016x}",
+            Self::DEFAULT_BOUNDARY_PREFIX,
+            random()
+        ))
+    }
+
+    /// Creates new `Builder` with a `WebKit` style boundary, like `----WebKitFormBoundaryWLHCs9qmcJJoyjKR`.
+    #[must_use]
+    pub fn webkit_style() -> Self {
+        Self::new().boundary_prefix(Self::WEBKIT_BOUNDARY_PREFIX)
+    }
+
+    /// Creates new `Builder` with boundary.
+    #[must_use]
+    pub fn with_boundary<S>(boundary: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            boundary: boundary.into(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Generates a new boundary with the prefix, followed by 16 random alphanumerics.
+    #[must_use]
+    pub fn boundary_prefix(mut self, prefix: &str) -> Self {
+        let mut boundary = String::with_capacity(prefix.len() + 16);
+        boundary.push_str(prefix);
+        for n in [random(), random()] {
+            boundary.extend(
+                n.to_le_bytes()
+                    .iter()
+                    .map(|b| char::from(ALPHANUMERIC[usize::from(*b) % ALPHANUMERIC.len()])),
+            );
+        }
+        self.boundary = boundary;
+        self
+    }
+
+    /// Gets the boundary.
+    #[must_use]
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Gets the value of `Content-Type` header, like `multipart/form-data; boundary=AaB03x`.
+    ///
+    /// The boundary is quoted if it contains the special characters, like space.
+    #[must_use]
+    pub fn content_type(&self) -> String {
+        if self.boundary.bytes().any(|b| TSPECIALS.contains(&b)) {
+            format!("multipart/form-data; boundary=\"{}\"", self.boundary)
+        } else {
+            format!("multipart/form-data; boundary={}", self.boundary)
+        }
+    }
+
+    /// Gets the `Content-Type` header, drops straight into request builders.
+    ///
+    /// Fails with `Error::InvalidHeader` if the boundary contains the characters
+    /// which are invalid in header value.
+    pub fn content_type_header(&self) -> Result<HeaderValue> {
+        HeaderValue::try_from(self.content_type()).map_err(|_| Error::InvalidHeader)
+    }
+
+    /// Appends a text field.
+    #[must_use]
+    pub fn text<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: AsRef<str>,
+        V: Into<Bytes>,
+    {
+        self.parts.push((
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"",
+                escape(name.as_ref())
+            ),
+            value.into(),
+        ));
+        self
+    }
+
+    /// Appends a file field.
+    #[must_use]
+    pub fn file<N, F, D>(mut self, name: N, filename: F, content_type: &mime::Mime, data: D) -> Self
+    where
+        N: AsRef<str>,
+        F: AsRef<str>,
+        D: Into<Bytes>,
+    {
+        self.parts.push((
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}",
+                escape(name.as_ref()),
+                escape(filename.as_ref()),
+                content_type
+            ),
+            data.into(),
+        ));
+        self
+    }
+
+    /// Encodes the parts into body.
+    #[must_use]
+    pub fn build(&self) -> Bytes {
+        let boundary = self.boundary.as_bytes();
+        let mut body = BytesMut::with_capacity(
+            self.parts
+                .iter()
+                .map(|(headers, data)| boundary.len() + headers.len() + data.len() + 10)
+                .sum::<usize>()
+                + boundary.len()
+                + 6,
+        );
+
+        for (headers, data) in &self.parts {
+            body.put_slice(&DASHES);
+            body.put_slice(boundary);
+            body.put_slice(&CRLF);
+            body.put_slice(headers.as_bytes());
+            body.put_slice(&CRLF);
+            body.put_slice(&CRLF);
+            body.put_slice(data);
+            body.put_slice(&CRLF);
+        }
+
+        body.put_slice(&DASHES);
+        body.put_slice(boundary);
+        body.put_slice(&DASHES);
+        body.put_slice(&CRLF);
+
+        body.freeze()
+    }
+}
+
+/// Escapes the quoted value of `Content-Disposition`, as browsers do.
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn random() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos()),
+    );
+    hasher.finish()
+}
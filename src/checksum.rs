@@ -0,0 +1,66 @@
+use std::fmt;
+
+#[cfg(feature = "sha2")]
+use sha2::Digest;
+
+/// Checksum algorithm of field data, see `Field::with_checksum`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Checksum {
+    /// CRC-32 (IEEE), like gzip members.
+    Crc32,
+    /// Adler-32, like zlib streams.
+    Adler32,
+    /// SHA-256, requires the `sha2` feature.
+    #[cfg(feature = "sha2")]
+    Sha256,
+}
+
+/// Updates the checksum per chunk.
+pub(crate) enum Hasher {
+    Crc32(crc32fast::Hasher),
+    Adler32(adler2::Adler32),
+    #[cfg(feature = "sha2")]
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    pub(crate) fn new(checksum: Checksum) -> Self {
+        match checksum {
+            Checksum::Crc32 => Self::Crc32(crc32fast::Hasher::new()),
+            Checksum::Adler32 => Self::Adler32(adler2::Adler32::new()),
+            #[cfg(feature = "sha2")]
+            Checksum::Sha256 => Self::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, buf: &[u8]) {
+        match self {
+            Self::Crc32(h) => h.update(buf),
+            Self::Adler32(h) => h.write_slice(buf),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(h) => h.update(buf),
+        }
+    }
+
+    /// Finalizes the checksum, in big-endian.
+    pub(crate) fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            Self::Adler32(h) => h.checksum().to_be_bytes().to_vec(),
+            #[cfg(feature = "sha2")]
+            Self::Sha256(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+impl fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Crc32(_) => "Crc32",
+            Self::Adler32(_) => "Adler32",
+            #[cfg(feature = "sha2")]
+            Self::Sha256(_) => "Sha256",
+        })
+    }
+}
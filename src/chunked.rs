@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+#[cfg(all(feature = "sync", not(feature = "async")))]
+use std::io::{self, Read};
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use futures_util::stream::Stream;
+
+/// Chunked, yields the data at exact chunk boundaries, for testing.
+///
+/// The delimiter-straddling cases can be reproduced, unlike a random chunk size.
+/// The rest of data is yielded at once when the sizes are exhausted.
+#[derive(Debug, Clone)]
+pub struct Chunked {
+    data: Bytes,
+    sizes: VecDeque<usize>,
+}
+
+impl Chunked {
+    /// Creates new `Chunked`, yields the whole data as a chunk.
+    #[must_use]
+    pub fn new<D>(data: D) -> Self
+    where
+        D: Into<Bytes>,
+    {
+        Self {
+            data: data.into(),
+            sizes: VecDeque::new(),
+        }
+    }
+
+    /// Sets the sizes of chunks in order.
+    ///
+    /// A zero size yields an empty chunk in async, it is skipped in sync.
+    #[must_use]
+    pub fn chunk_sizes(mut self, sizes: Vec<usize>) -> Self {
+        self.sizes = sizes.into();
+        self
+    }
+
+    /// Gets the size of data has not been yielded.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Splits the next chunk, `None` if data is exhausted.
+    fn next_chunk(&mut self, max: usize) -> Option<Bytes> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let size = self
+            .sizes
+            .pop_front()
+            .unwrap_or(self.data.len())
+            .min(self.data.len());
+
+        // the rest of chunk is yielded next time
+        if size > max {
+            self.sizes.push_front(size - max);
+        }
+
+        Some(self.data.split_to(size.min(max)))
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl Stream for Chunked {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.next_chunk(usize::MAX).map(Ok))
+    }
+}
+
+#[cfg(all(feature = "sync", not(feature = "async")))]
+impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // an empty read is the end of data
+        while self.sizes.front() == Some(&0) {
+            self.sizes.pop_front();
+        }
+
+        let Some(chunk) = self.next_chunk(buf.len()) else {
+            return Ok(0);
+        };
+
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::{FieldData, FieldMeta};
+
+/// A collected file field.
+#[derive(Debug)]
+pub struct CollectedFile {
+    /// The metadata of Field.
+    pub meta: FieldMeta,
+    /// The data of Field, in memory or spooled to a temp file.
+    pub data: FieldData,
+}
+
+/// The entire form, text fields and file fields by name.
+#[derive(Debug, Default)]
+pub struct Form {
+    pub(crate) texts: HashMap<String, Vec<String>>,
+    pub(crate) files: HashMap<String, Vec<CollectedFile>>,
+}
+
+impl Form {
+    /// Gets the first text value by name.
+    #[must_use]
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.texts(name).first().map(String::as_str)
+    }
+
+    /// Gets all text values by name.
+    #[must_use]
+    pub fn texts(&self, name: &str) -> &[String] {
+        self.texts.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gets the first file by name.
+    #[must_use]
+    pub fn file(&self, name: &str) -> Option<&CollectedFile> {
+        self.files(name).first()
+    }
+
+    /// Gets all files by name.
+    #[must_use]
+    pub fn files(&self, name: &str) -> &[CollectedFile] {
+        self.files.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Inserts a text value.
+    pub(crate) fn push_text(&mut self, name: String, value: String) {
+        self.texts.entry(name).or_default().push(value);
+    }
+
+    /// Inserts a file.
+    pub(crate) fn push_file(&mut self, meta: FieldMeta, data: FieldData) {
+        self.files
+            .entry(meta.name.clone())
+            .or_default()
+            .push(CollectedFile { meta, data });
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use aes::Aes256;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use bytes::{Bytes, BytesMut};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+
+use crate::{Error, Field, Result};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+/// The cipher of encrypted field data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Cipher {
+    /// AES-256 in CTR mode with a 128-bit big-endian counter, 16-byte nonce.
+    ///
+    /// Decrypted incrementally, chunk by chunk.
+    Aes256Ctr,
+    /// AES-256-GCM, 12-byte nonce, the 16-byte tag is at the end of data.
+    ///
+    /// The whole data is buffered to verify the tag, it's yielded at once.
+    Aes256Gcm,
+}
+
+pub(crate) enum Decryptor {
+    Ctr(Box<Aes256Ctr>),
+    Gcm {
+        cipher: Box<Aes256Gcm>,
+        nonce: Nonce<<Aes256Gcm as aes_gcm::AeadCore>::NonceSize>,
+        buf: BytesMut,
+    },
+    Done,
+}
+
+/// Decrypted chunks of field data.
+pub struct Decrypt<'a, T> {
+    pub(crate) field: &'a mut Field<T>,
+    pub(crate) decryptor: Decryptor,
+    pub(crate) eof: bool,
+}
+
+impl<T> Decrypt<'_, T> {
+    /// Decrypts a chunk, authenticated modes keep it for the tag.
+    pub(crate) fn update(&mut self, buf: &[u8]) -> Option<Bytes> {
+        match &mut self.decryptor {
+            Decryptor::Ctr(cipher) => {
+                let mut chunk = buf.to_vec();
+                cipher.apply_keystream(&mut chunk);
+                Some(chunk.into())
+            }
+            Decryptor::Gcm { buf: rest, .. } => {
+                rest.extend_from_slice(buf);
+                None
+            }
+            Decryptor::Done => None,
+        }
+    }
+
+    /// Verifies the tag and decrypts the buffered data.
+    pub(crate) fn finish(&mut self) -> Option<Result<Bytes>> {
+        match std::mem::replace(&mut self.decryptor, Decryptor::Done) {
+            Decryptor::Gcm { cipher, nonce, buf } => Some(
+                cipher
+                    .decrypt(&nonce, buf.as_ref())
+                    .map(Into::into)
+                    .map_err(|_| Error::Decrypt("authentication tag mismatch")),
+            ),
+            Decryptor::Ctr(_) | Decryptor::Done => None,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Decrypt<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decrypt")
+            .field("field", &self.field)
+            .field("eof", &self.eof)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Field<T> {
+    /// Reads field data as decrypted chunks.
+    ///
+    /// Fails with `Error::Decrypt` if the length of key or nonce is invalid.
+    pub fn decrypt(&mut self, key: &[u8], nonce: &[u8], alg: Cipher) -> Result<Decrypt<'_, T>> {
+        let decryptor = match alg {
+            Cipher::Aes256Ctr => Decryptor::Ctr(Box::new(
+                Aes256Ctr::new_from_slices(key, nonce)
+                    .map_err(|_| Error::Decrypt("invalid key or nonce length"))?,
+            )),
+            Cipher::Aes256Gcm => {
+                if nonce.len() != 12 {
+                    return Err(Error::Decrypt("invalid nonce length"));
+                }
+                Decryptor::Gcm {
+                    cipher: Box::new(
+                        Aes256Gcm::new_from_slice(key)
+                            .map_err(|_| Error::Decrypt("invalid key length"))?,
+                    ),
+                    nonce: *Nonce::from_slice(nonce),
+                    buf: BytesMut::new(),
+                }
+            }
+        };
+
+        Ok(Decrypt {
+            field: self,
+            decryptor,
+            eof: false,
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Buf, Bytes, BytesMut};
+
+use crate::Field;
+
+/// Base64 encoded chunks of field data, pads at the end only.
+pub struct Base64Stream<'a, T> {
+    pub(crate) field: &'a mut Field<T>,
+    pub(crate) rest: BytesMut,
+    pub(crate) eof: bool,
+}
+
+impl<T> Base64Stream<'_, T> {
+    /// Encodes the complete 3-byte groups, keeps the rest for the next chunk.
+    pub(crate) fn encode(&mut self, buf: &[u8]) -> Option<Bytes> {
+        self.rest.extend_from_slice(buf);
+        let n = self.rest.len() / 3 * 3;
+        if n == 0 {
+            return None;
+        }
+        let chunk = STANDARD.encode(&self.rest[..n]);
+        self.rest.advance(n);
+        Some(chunk.into())
+    }
+
+    /// Encodes the rest bytes with padding.
+    pub(crate) fn finish(&mut self) -> Option<Bytes> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let chunk = STANDARD.encode(&self.rest);
+        self.rest.clear();
+        Some(chunk.into())
+    }
+}
+
+impl<T> fmt::Debug for Base64Stream<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Base64Stream")
+            .field("field", &self.field)
+            .field("rest", &self.rest.len())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+impl<T> Field<T> {
+    /// Reads field data as base64 encoded chunks, without buffering the whole data.
+    pub fn base64_stream(&mut self) -> Base64Stream<'_, T> {
+        Base64Stream {
+            field: self,
+            rest: BytesMut::with_capacity(2),
+            eof: false,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::{fmt, sync::Arc};
+
 use thiserror::Error;
 
 /// Form-data Error
@@ -11,6 +13,11 @@ pub enum Error {
     #[error(transparent)]
     BoxError(#[from] Box<dyn std::error::Error + Send + Sync>),
 
+    /// JSON Error
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
     /// Invalid part header
     #[error("invalid part header")]
     InvalidHeader,
@@ -35,6 +42,13 @@ pub enum Error {
     #[error("parts is too many, limit to `{0}`")]
     PartsTooMany(usize),
 
+    /// Parts too few
+    #[error("parts is too few, at least `{min}`")]
+    TooFewParts {
+        /// The min number of parts.
+        min: usize,
+    },
+
     /// Fields too many
     #[error("fields is too many, limit to `{0}`")]
     FieldsTooMany(usize),
@@ -43,11 +57,236 @@ pub enum Error {
     #[error("files is too many, limit to `{0}`")]
     FilesTooMany(usize),
 
+    /// Stream is incomplete
+    #[error("stream is incomplete, the closing boundary is missing")]
+    IncompleteStream,
+
+    /// Stream ends inside the headers of a part
+    #[error("stream is incomplete, the headers of part are truncated")]
+    IncompleteHeaders,
+
+    /// Body is not multipart
+    #[error("body is not multipart")]
+    NotMultipart,
+
+    /// Part is suspicious
+    #[error("part is suspicious, header to body ratio is over `{0}`")]
+    SuspiciousPart(f64),
+
+    /// Field data exceeds the declared content length
+    #[error("field data `{actual}` exceeds the declared content length `{declared}`")]
+    LengthMismatch {
+        /// The declared `Content-Length`.
+        declared: u64,
+        /// The size of field data.
+        actual: u64,
+    },
+
+    /// Part headers too many
+    #[error("part headers is too many, limit to `{limit}`")]
+    TooManyPartHeaders {
+        /// The max number of headers.
+        limit: usize,
+    },
+
     /// Field name is too long
     #[error("field name is too long, limit to `{0}`")]
     FieldNameTooLong(usize),
 
+    /// Field name is reserved
+    #[error("field name `{0}` is reserved")]
+    ReservedFieldName(String),
+
+    /// Content type of file is not allowed
+    #[error("content type `{content_type}` of `{name}` is not allowed")]
+    ContentTypeNotAllowed {
+        /// The name of field.
+        name: String,
+        /// The content type of file.
+        content_type: mime::Mime,
+    },
+
+    /// Values with the same field name too many
+    #[error("values of `{name}` is too many, limit to `{max}`")]
+    ValuesTooMany {
+        /// The name of field.
+        name: String,
+        /// The max number of values.
+        max: usize,
+    },
+
+    /// File part is unexpected
+    #[error("file `{0}` is unexpected")]
+    UnexpectedFile(String),
+
+    /// Content disposition type is not `form-data`
+    #[error("content disposition type must be `form-data`")]
+    InvalidDispositionType,
+
+    /// Field name is missing
+    #[error("field name is missing")]
+    MissingFieldName,
+
+    /// Filename is duplicated
+    #[error("filename is duplicated")]
+    DuplicateFilename,
+
+    /// Content transfer encoding is not allowed
+    #[error("content transfer encoding `{0}` is not allowed")]
+    InvalidTransferEncoding(String),
+
+    /// Field is still active
+    #[error("field is still active, read it before pulling the next field")]
+    FieldStillActive,
+
+    /// Text field is not valid UTF-8
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+
+    /// File referenced by the GraphQL `map` is missing
+    #[cfg(feature = "json")]
+    #[error("file `{0}` of graphql map is missing")]
+    MissingMappedFile(String),
+
+    /// File part is not referenced by the GraphQL `map`
+    #[cfg(feature = "json")]
+    #[error("file `{0}` is not referenced by graphql map")]
+    MapMismatch(String),
+
+    /// GraphQL `map` is missing or not read
+    #[cfg(feature = "json")]
+    #[error("graphql map is missing")]
+    MissingMap,
+
+    /// Timed out
+    #[cfg(feature = "tokio")]
+    #[error("timed out waiting for the next field")]
+    Timeout,
+
+    /// Field data cannot be decrypted
+    #[cfg(feature = "decrypt")]
+    #[error("field data cannot be decrypted, {0}")]
+    Decrypt(&'static str),
+
+    /// Field is consumed
+    #[error("field is consumed")]
+    FieldConsumed,
+
+    /// Error with context
+    #[error("{0}: {1}")]
+    Context(String, #[source] Box<Error>),
+
     /// Try Lock Error
     #[error("`{0}`")]
     TryLockError(String),
 }
+
+impl<T> From<std::sync::TryLockError<T>> for Error {
+    fn from(e: std::sync::TryLockError<T>) -> Self {
+        match e {
+            std::sync::TryLockError::WouldBlock => Self::TryLockError(
+                "state is locked, a guard of `FormData::state()` or `Field::state_mut()` may be held while polling"
+                    .to_string(),
+            ),
+            std::sync::TryLockError::Poisoned(e) => Self::TryLockError(e.to_string()),
+        }
+    }
+}
+
+impl Error {
+    /// Wraps the error with context.
+    #[must_use]
+    pub fn context<C>(self, context: C) -> Self
+    where
+        C: Into<String>,
+    {
+        Self::Context(context.into(), Box::new(self))
+    }
+
+    /// Duplicates the error for the error context, the variant is kept.
+    ///
+    /// The IO, boxed and JSON errors are rebuilt from their kind and message.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Stream(e) => Self::Stream(std::io::Error::new(e.kind(), e.to_string())),
+            Self::BoxError(e) => Self::BoxError(e.to_string().into()),
+            #[cfg(feature = "json")]
+            Self::Json(e) => Self::Json(serde::de::Error::custom(e)),
+            Self::InvalidHeader => Self::InvalidHeader,
+            Self::InvalidContentDisposition => Self::InvalidContentDisposition,
+            Self::PayloadTooLarge(max) => Self::PayloadTooLarge(*max),
+            Self::FileTooLarge(max) => Self::FileTooLarge(*max),
+            Self::FieldTooLarge(max) => Self::FieldTooLarge(*max),
+            Self::PartsTooMany(max) => Self::PartsTooMany(*max),
+            Self::TooFewParts { min } => Self::TooFewParts { min: *min },
+            Self::FieldsTooMany(max) => Self::FieldsTooMany(*max),
+            Self::FilesTooMany(max) => Self::FilesTooMany(*max),
+            Self::IncompleteStream => Self::IncompleteStream,
+            Self::IncompleteHeaders => Self::IncompleteHeaders,
+            Self::NotMultipart => Self::NotMultipart,
+            Self::SuspiciousPart(max) => Self::SuspiciousPart(*max),
+            Self::LengthMismatch { declared, actual } => Self::LengthMismatch {
+                declared: *declared,
+                actual: *actual,
+            },
+            Self::TooManyPartHeaders { limit } => Self::TooManyPartHeaders { limit: *limit },
+            Self::FieldNameTooLong(max) => Self::FieldNameTooLong(*max),
+            Self::ReservedFieldName(name) => Self::ReservedFieldName(name.clone()),
+            Self::ContentTypeNotAllowed { name, content_type } => Self::ContentTypeNotAllowed {
+                name: name.clone(),
+                content_type: content_type.clone(),
+            },
+            Self::ValuesTooMany { name, max } => Self::ValuesTooMany {
+                name: name.clone(),
+                max: *max,
+            },
+            Self::UnexpectedFile(name) => Self::UnexpectedFile(name.clone()),
+            Self::InvalidDispositionType => Self::InvalidDispositionType,
+            Self::MissingFieldName => Self::MissingFieldName,
+            Self::DuplicateFilename => Self::DuplicateFilename,
+            Self::InvalidTransferEncoding(encoding) => {
+                Self::InvalidTransferEncoding(encoding.clone())
+            }
+            Self::FieldStillActive => Self::FieldStillActive,
+            Self::Utf8(e) => Self::Utf8(e.clone()),
+            #[cfg(feature = "json")]
+            Self::MissingMappedFile(name) => Self::MissingMappedFile(name.clone()),
+            #[cfg(feature = "json")]
+            Self::MapMismatch(name) => Self::MapMismatch(name.clone()),
+            #[cfg(feature = "json")]
+            Self::MissingMap => Self::MissingMap,
+            #[cfg(feature = "tokio")]
+            Self::Timeout => Self::Timeout,
+            #[cfg(feature = "decrypt")]
+            Self::Decrypt(reason) => Self::Decrypt(reason),
+            Self::FieldConsumed => Self::FieldConsumed,
+            Self::Context(context, e) => Self::Context(context.clone(), Box::new(e.duplicate())),
+            Self::TryLockError(e) => Self::TryLockError(e.clone()),
+        }
+    }
+}
+
+/// The last error produced by the stream, with the progress of parsing at the time.
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    /// The error, shared by the clones of context.
+    pub error: Arc<Error>,
+    /// The number of parts.
+    pub total: usize,
+    /// The number of files.
+    pub files: usize,
+    /// The number of fields.
+    pub fields: usize,
+    /// The size of read data.
+    pub length: u64,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, after {} parts ({} files, {} fields) and {} bytes",
+            self.error, self.total, self.files, self.fields, self.length
+        )
+    }
+}
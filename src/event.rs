@@ -0,0 +1,55 @@
+use std::fmt;
+
+use bytes::Bytes;
+
+use crate::{Field, FieldMeta, FormData};
+
+/// Event of parsing form-data
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A part is started, with the metadata of Field.
+    PartStart(Box<FieldMeta>),
+    /// The payload data of current part.
+    Data(Bytes),
+    /// Current part is ended, with the payload size of Field.
+    PartEnd {
+        /// The payload size of Field.
+        length: usize,
+    },
+    /// Whole stream is ended.
+    Complete,
+}
+
+/// Events, flattens `FormData` and `Field` into a single stream of `Event`.
+pub struct Events<T> {
+    pub(crate) form: FormData<T>,
+    pub(crate) field: Option<Field<T>>,
+    pub(crate) completed: bool,
+}
+
+impl<T> Events<T> {
+    /// Creates new `Events`.
+    #[must_use]
+    pub fn new(form: FormData<T>) -> Self {
+        Self {
+            form,
+            field: None,
+            completed: false,
+        }
+    }
+
+    /// Gets the `FormData`.
+    #[must_use]
+    pub fn form(&self) -> &FormData<T> {
+        &self.form
+    }
+}
+
+impl<T> fmt::Debug for Events<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("field", &self.field)
+            .field("completed", &self.completed)
+            .finish_non_exhaustive()
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::State;
+use bytes::Bytes;
+use tracing::{trace, trace_span};
+
+use crate::{Error, Limits, Result, State};
 
 /// Field
 pub struct Field<T> {
@@ -20,6 +23,16 @@ pub struct Field<T> {
     /// The extras headers of Field, optinal.
     pub headers: Option<http::HeaderMap>,
     pub(crate) state: Option<Arc<Mutex<State<T>>>>,
+    pub(crate) peeked: Option<Bytes>,
+    pub(crate) header_size: usize,
+    /// The offset of field data in the whole stream.
+    pub(crate) body_start: Option<u64>,
+    pub(crate) fully_read: bool,
+    pub(crate) raw_content_disposition: Option<http::HeaderValue>,
+    /// The span of part, entered while polling field data.
+    pub(crate) span: tracing::Span,
+    #[cfg(feature = "checksum")]
+    pub(crate) hasher: Option<crate::checksum::Hasher>,
 }
 
 impl<T> Field<T> {
@@ -34,9 +47,57 @@ impl<T> Field<T> {
             content_type: None,
             headers: None,
             state: None,
+            peeked: None,
+            header_size: 0,
+            body_start: None,
+            fully_read: false,
+            raw_content_disposition: None,
+            span: tracing::Span::none(),
+            #[cfg(feature = "checksum")]
+            hasher: None,
         }
     }
 
+    /// Gets the name of Field.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the filename of Field, optinal.
+    #[must_use]
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Gets the raw bytes of `Content-Disposition` header, as received.
+    #[must_use]
+    pub fn raw_content_disposition(&self) -> Option<&[u8]> {
+        self.raw_content_disposition
+            .as_ref()
+            .map(http::HeaderValue::as_bytes)
+    }
+
+    /// Computes the checksum of field data inline, as it is read.
+    ///
+    /// Sets it before reading, the data has been read is not included.
+    #[cfg(feature = "checksum")]
+    #[must_use]
+    pub fn with_checksum(mut self, checksum: crate::Checksum) -> Self {
+        self.hasher.replace(crate::checksum::Hasher::new(checksum));
+        self
+    }
+
+    /// Finalizes the checksum of the read data, in big-endian,
+    /// returns empty if no checksum is set.
+    #[cfg(feature = "checksum")]
+    pub fn finalize_checksum(&mut self) -> Vec<u8> {
+        self.hasher
+            .take()
+            .map(crate::checksum::Hasher::finalize)
+            .unwrap_or_default()
+    }
+
     /// Gets mutable headers.
     #[must_use]
     pub fn headers_mut(&mut self) -> &mut Option<http::HeaderMap> {
@@ -54,6 +115,168 @@ impl<T> Field<T> {
     pub fn consumed(&self) -> bool {
         self.state.is_none()
     }
+
+    /// Gets the body is read to its natural end, not skipped by `ignore`.
+    #[must_use]
+    pub fn fully_read(&self) -> bool {
+        self.fully_read
+    }
+
+    /// Gets the start and end offsets of field data in the whole stream,
+    /// once Field is consumed.
+    #[must_use]
+    pub fn body_range(&self) -> Option<(u64, u64)> {
+        if !self.consumed() {
+            return None;
+        }
+
+        let start = self.body_start?;
+        Some((start, start.saturating_add(self.length as u64)))
+    }
+
+    /// Gets the limits of the state, fails if Field is consumed.
+    pub fn limits(&self) -> Result<Limits> {
+        Ok(self
+            .state
+            .as_ref()
+            .ok_or(Error::FieldConsumed)?
+            .try_lock()
+            .map_err(Error::from)?
+            .limits
+            .clone())
+    }
+
+    /// Gets the declared `Content-Length` of Field, optinal.
+    #[must_use]
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .as_ref()?
+            .get(http::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Creates a request builder with the headers of Field, for proxying the part.
+    ///
+    /// The `Content-Type` is set from `content_type`, then the extras headers are added.
+    #[must_use]
+    pub fn to_request_builder(&self) -> http::request::Builder {
+        let mut builder = http::Request::builder();
+
+        if let Some(headers) = builder.headers_mut() {
+            if let Some(value) = self
+                .content_type
+                .as_ref()
+                .and_then(|mime| http::HeaderValue::from_str(mime.as_ref()).ok())
+            {
+                headers.insert(http::header::CONTENT_TYPE, value);
+            }
+
+            if let Some(extras) = &self.headers {
+                headers.extend(extras.clone());
+            }
+        }
+
+        builder
+    }
+
+    /// Checks the size limits before taking `l` more bytes of field data.
+    pub(crate) fn check_size(&self, limits: &Limits, l: usize) -> Result<()> {
+        let length = self.length.saturating_add(l);
+
+        if self.filename.is_some() {
+            if let Some(max) = limits.checked_file_size(length) {
+                return Err(Error::FileTooLarge(max));
+            }
+        } else if let Some(max) = limits.checked_field_size(length) {
+            return Err(Error::FieldTooLarge(max));
+        }
+
+        // exceeds the declared `Content-Length`
+        if limits.enforce_content_length {
+            let actual = u64::try_from(length).unwrap_or(u64::MAX);
+            if let Some(declared) = self.content_length().filter(|declared| actual > *declared) {
+                return Err(Error::LengthMismatch { declared, actual });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the field which is ended, while the state is held.
+    pub(crate) fn check_ended(&self, state: &mut State<T>) -> Result<()> {
+        #[cfg(feature = "json")]
+        if let Some(graphql) = state.graphql.as_mut().filter(|_| self.is_graphql_map()) {
+            graphql.parse_map()?;
+        }
+
+        state.take_part_error()
+    }
+
+    /// Maps the error with the error context, records it as the last error.
+    pub(crate) fn map_error(&self, e: Error) -> Error {
+        match self.state.as_ref().map(|state| state.try_lock()) {
+            Some(Ok(mut state)) => state.record_error(e),
+            _ => e,
+        }
+    }
+
+    /// Creates the span of part with its name, index and filename.
+    ///
+    /// The name is recorded as `field`, `name` is taken by the span name in JSON logs.
+    pub(crate) fn init_span(&mut self) {
+        self.span = trace_span!(
+            "part",
+            field = %self.name,
+            index = self.index,
+            filename = self.filename.as_deref(),
+        );
+        self.span.in_scope(|| {
+            trace!(
+                content_type = self.content_type.as_ref().map(mime::Mime::essence_str),
+                header_size = self.header_size,
+                "part parsed"
+            );
+        });
+    }
+
+    /// Gets a snapshot of the metadata of Field.
+    #[must_use]
+    pub fn meta(&self) -> FieldMeta {
+        FieldMeta {
+            index: self.index,
+            length: self.length,
+            name: self.name.clone(),
+            filename: self.filename.clone(),
+            content_type: self.content_type.clone(),
+            headers: self.headers.clone(),
+        }
+    }
+
+    /// The field is the GraphQL `map`.
+    #[cfg(feature = "json")]
+    pub(crate) fn is_graphql_map(&self) -> bool {
+        self.filename.is_none() && self.name == crate::graphql::MAP
+    }
+}
+
+/// Owned metadata of Field, detached from the state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMeta {
+    /// The payload size of Field.
+    pub length: usize,
+    /// The index of Field.
+    pub index: usize,
+    /// The name of Field.
+    pub name: String,
+    /// The filename of Field, optinal.
+    pub filename: Option<String>,
+    /// The `content_type` of Field, optinal.
+    pub content_type: Option<mime::Mime>,
+    /// The extras headers of Field, optinal.
+    pub headers: Option<http::HeaderMap>,
 }
 
 impl<T> fmt::Debug for Field<T> {
@@ -66,6 +289,6 @@ impl<T> fmt::Debug for Field<T> {
             .field("length", &self.length)
             .field("headers", &self.headers)
             .field("consumed", &self.state.is_none())
-            .finish()
+            .finish_non_exhaustive()
     }
 }
@@ -1,8 +1,36 @@
 #![allow(clippy::module_name_repetitions)]
 
-use std::sync::{Arc, Mutex};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
-use crate::{Error, Limits, Result, State};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "json")]
+use crate::graphql::GraphQl;
+#[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+use crate::rate::RateLimit;
+use crate::{
+    BoundarySearcher, Error, ErrorContext, Events, FieldMeta, Limits, Result, State, Strict, Take,
+};
+
+/// How to pull the next field while the previous field is still active.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReadMode {
+    /// Waits until the previous field is consumed, fields must be read sequentially.
+    ///
+    /// In sync, it is same as `Strict`, since nothing can consume the field meanwhile.
+    #[default]
+    Sequential,
+    /// Fails with `Error::FieldStillActive`.
+    Strict,
+    /// Drains the rest data of the previous field into buffer,
+    /// so both fields can be read independently.
+    ///
+    /// The size limits are still applied to the buffered data.
+    Buffered,
+}
 
 /// `FormData`
 pub struct FormData<T> {
@@ -30,7 +58,23 @@ impl<T> FormData<T> {
         }
     }
 
+    /// Flattens into a single stream of `Event`.
+    #[must_use]
+    pub fn events(self) -> Events<T> {
+        Events::new(self)
+    }
+
+    /// Yields at most `n` fields, then stops cleanly.
+    ///
+    /// Calls `Take::drain` to read the rest of body, so the connection can be reused.
+    #[must_use]
+    pub fn take(self, n: usize) -> Take<T> {
+        Take::new(self, n)
+    }
+
     /// Gets the state.
+    ///
+    /// Don't hold a guard of it while polling, the stream fails with `Error::TryLockError`.
     #[must_use]
     pub fn state(&self) -> Arc<Mutex<State<T>>> {
         self.state.clone()
@@ -40,10 +84,164 @@ impl<T> FormData<T> {
     pub fn set_max_buf_size(&self, max: usize) -> Result<()> {
         self.state
             .try_lock()
-            .map_err(|e| Error::TryLockError(e.to_string()))?
+            .map_err(Error::from)?
             .limits_mut()
             .buffer_size = max;
 
         Ok(())
     }
+
+    /// Paces the reads of the whole stream, at most `bytes_per_sec` on average.
+    ///
+    /// In async, it requires the `tokio` timer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is zero.
+    #[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+    pub fn set_rate_limit(&self, bytes_per_sec: u64) -> Result<()> {
+        self.state
+            .try_lock()
+            .map_err(Error::from)?
+            .rate_limit
+            .replace(RateLimit::new(bytes_per_sec));
+
+        Ok(())
+    }
+
+    /// Maps every error produced by the stream, such as attaching request context.
+    ///
+    /// The errors of fields are mapped too.
+    pub fn with_error_context<F>(self, f: F) -> Result<Self>
+    where
+        F: Fn(Error) -> Error + Send + Sync + 'static,
+    {
+        self.state
+            .try_lock()
+            .map_err(Error::from)?
+            .error_mapper
+            .replace(Arc::new(f));
+
+        Ok(self)
+    }
+
+    /// Runs the hook when each field is fully consumed, with the final length.
+    pub fn on_field_complete<F>(self, f: F) -> Result<Self>
+    where
+        F: FnMut(&FieldMeta, u64) + Send + 'static,
+    {
+        self.state
+            .try_lock()
+            .map_err(Error::from)?
+            .on_field_complete
+            .replace(Box::new(f));
+
+        Ok(self)
+    }
+
+    /// Sets how to pull the next field while the previous field is still active.
+    pub fn read_mode(self, mode: ReadMode) -> Result<Self> {
+        self.state.try_lock().map_err(Error::from)?.read_mode = mode;
+
+        Ok(self)
+    }
+
+    /// Sets the dir of spooled temp files, defaults to `std::env::temp_dir()`.
+    pub fn with_tempdir<P>(self, dir: P) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        self.state
+            .try_lock()
+            .map_err(Error::from)?
+            .tempdir
+            .replace(dir.into());
+
+        Ok(self)
+    }
+
+    /// Sets the searcher of the boundary delimiter, defaults to `MemmemSearcher`.
+    pub fn with_searcher<S>(self, searcher: S) -> Result<Self>
+    where
+        S: BoundarySearcher + 'static,
+    {
+        self.state.try_lock().map_err(Error::from)?.searcher = Box::new(searcher);
+
+        Ok(self)
+    }
+
+    /// Enables [graphql-multipart-request] mode.
+    ///
+    /// The `operations` and `map` fields without `Content-Type` are treated as `application/json`,
+    /// every file referenced by `map` must be present, see `Error::MissingMappedFile`,
+    /// and every file after `map` must be referenced by it, see `Error::MapMismatch`.
+    ///
+    /// [graphql-multipart-request]: <https://github.com/jaydenseric/graphql-multipart-request-spec>
+    #[cfg(feature = "json")]
+    pub fn graphql_mode(self) -> Result<Self> {
+        self.state
+            .try_lock()
+            .map_err(Error::from)?
+            .graphql
+            .replace(GraphQl::default());
+
+        Ok(self)
+    }
+
+    /// Enables `multipart/byteranges` mode, for parsing the response of range requests.
+    ///
+    /// The `Content-Disposition` is optional, `Field::name` is empty without it.
+    /// The `Content-Range` and `Content-Type` headers are kept in `Field::headers`.
+    /// The parts are counted as fields, see `Limits::field_size`.
+    pub fn byteranges_mode(self) -> Result<Self> {
+        self.state.try_lock().map_err(Error::from)?.byteranges = true;
+
+        Ok(self)
+    }
+
+    /// Sets the strict checks on the headers of parts, lenient by default.
+    pub fn with_strict(self, strict: Strict) -> Result<Self> {
+        self.state.try_lock().map_err(Error::from)?.strict = strict;
+
+        Ok(self)
+    }
+
+    /// Enables or disables all strict checks of [rfc7578].
+    ///
+    /// [rfc7578]: <https://tools.ietf.org/html/rfc7578>
+    pub fn rfc7578_strict(self, strict: bool) -> Result<Self> {
+        self.with_strict(if strict {
+            Strict::rfc7578()
+        } else {
+            Strict::default()
+        })
+    }
+
+    /// Gets the last error produced by the stream, with the progress of parsing at the time.
+    ///
+    /// The errors of fields are recorded too, `None` if the state is locked.
+    #[must_use]
+    pub fn last_error_context(&self) -> Option<ErrorContext> {
+        self.state.try_lock().ok()?.last_error.clone()
+    }
+
+    /// Maps the error with the error context, records it as the last error.
+    pub(crate) fn map_error(&self, e: Error) -> Error {
+        match self.state.try_lock() {
+            Ok(mut state) => state.record_error(e),
+            Err(_) => e,
+        }
+    }
+
+    /// Gets the limits.
+    pub fn limits(&self) -> Result<Limits> {
+        Ok(self.state.try_lock().map_err(Error::from)?.limits.clone())
+    }
+
+    /// Sets the limits, changes apply to the parts which have not been read.
+    pub fn set_limits(&self, limits: Limits) -> Result<()> {
+        *self.state.try_lock().map_err(Error::from)?.limits_mut() = limits;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,84 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::BytesMut;
+
+use crate::{Error, Result};
+
+/// The field of GraphQL operations.
+pub(crate) const OPERATIONS: &str = "operations";
+/// The field maps files to the variables of operations.
+pub(crate) const MAP: &str = "map";
+
+/// The state of [graphql-multipart-request].
+///
+/// [graphql-multipart-request]: <https://github.com/jaydenseric/graphql-multipart-request-spec>
+#[derive(Debug, Default)]
+pub(crate) struct GraphQl {
+    /// The data of `map` field.
+    map: BytesMut,
+    /// The file keys referenced by `map`.
+    keys: Vec<String>,
+    /// The `map` field is parsed.
+    mapped: bool,
+    /// The names of file parts.
+    files: HashSet<String>,
+}
+
+impl GraphQl {
+    /// The `operations` and `map` fields are JSON.
+    pub(crate) fn content_type(name: &str) -> Option<mime::Mime> {
+        (name == OPERATIONS || name == MAP).then_some(mime::APPLICATION_JSON)
+    }
+
+    /// Records a file part, it must be referenced by `map`,
+    /// the files before `map` are checked once it is parsed.
+    pub(crate) fn file(&mut self, name: &str) -> Result<()> {
+        if self.mapped && !self.is_mapped(name) {
+            return Err(Error::MapMismatch(name.to_string()));
+        }
+        self.files.insert(name.to_string());
+        Ok(())
+    }
+
+    /// The file is referenced by `map`.
+    fn is_mapped(&self, name: &str) -> bool {
+        self.keys
+            .binary_search_by(|key| key.as_str().cmp(name))
+            .is_ok()
+    }
+
+    /// Checks every recorded file part is referenced by `map`.
+    fn check_files(&self) -> Result<()> {
+        match self.files.iter().find(|name| !self.is_mapped(name)) {
+            Some(name) => Err(Error::MapMismatch(name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Buffers the data of `map` field.
+    pub(crate) fn extend_map(&mut self, buf: &[u8]) {
+        self.map.extend_from_slice(buf);
+    }
+
+    /// Parses the keys of `map` field, when it is fully read.
+    pub(crate) fn parse_map(&mut self) -> Result<()> {
+        let map = serde_json::from_slice::<HashMap<String, Vec<String>>>(&self.map.split())?;
+        self.keys = map.into_keys().collect();
+        self.keys.sort_unstable();
+        self.mapped = true;
+        self.check_files()
+    }
+
+    /// Checks `map` is parsed, the file parts match the keys of `map`.
+    pub(crate) fn check(&self) -> Result<()> {
+        if !self.mapped {
+            return Err(Error::MissingMap);
+        }
+        self.check_files()?;
+
+        match self.keys.iter().find(|key| !self.files.contains(*key)) {
+            Some(key) => Err(Error::MissingMappedFile(key.clone())),
+            None => Ok(()),
+        }
+    }
+}
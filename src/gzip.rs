@@ -0,0 +1,157 @@
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use std::{
+    error::Error as StdError,
+    io::{self, BufRead, Read},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use bytes::{Buf, Bytes, BytesMut};
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use flate2::bufread::MultiGzDecoder;
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use futures_util::stream::Stream;
+
+use crate::FormData;
+#[cfg(all(feature = "async", not(feature = "sync")))]
+use crate::Limits;
+
+/// Inflates a gzipped request body for [`FormData`], `Content-Encoding: gzip`.
+///
+/// The data is inflated on demand, at most `buffer_size` bytes per poll,
+/// so the `stream_size` limit applies to the inflated data before it grows.
+/// All members of a multi-member body are inflated.
+///
+/// [`FormData`]: crate::FormData
+#[cfg(all(feature = "async", not(feature = "sync")))]
+pub struct GzipStream<S> {
+    inner: S,
+    decoder: MultiGzDecoder<Pending>,
+    /// The output buffer, the inflated data is split off.
+    buf: BytesMut,
+    buffer_size: usize,
+}
+
+/// The compressed chunks which have not been inflated.
+///
+/// Reading it fails with `WouldBlock` until the next chunk is pushed.
+#[cfg(all(feature = "async", not(feature = "sync")))]
+#[derive(Debug, Default)]
+struct Pending {
+    chunk: Bytes,
+    eof: bool,
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl Read for Pending {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.chunk[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl BufRead for Pending {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.chunk.is_empty() && !self.eof {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        Ok(&self.chunk)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.chunk.advance(amt);
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl<S> GzipStream<S> {
+    /// Creates new `GzipStream`, yields at most `Limits::DEFAULT_BUFFER_SIZE` bytes per poll.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            decoder: MultiGzDecoder::new(Pending::default()),
+            buf: BytesMut::new(),
+            buffer_size: Limits::DEFAULT_BUFFER_SIZE,
+        }
+    }
+
+    /// Sets the max size of inflated data yielded per poll.
+    #[must_use]
+    pub fn buffer_size(mut self, max: usize) -> Self {
+        self.buffer_size = max.max(1);
+        self
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl<S> std::fmt::Debug for GzipStream<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GzipStream")
+            .field("buffer_size", &self.buffer_size)
+            .field("eof", &self.decoder.get_ref().eof)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl<S, B, E> Stream for GzipStream<S>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: Into<Bytes>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let size = this.buffer_size;
+
+        // the written bytes are split off, the rest is reused
+        if this.buf.len() < size {
+            this.buf.resize(size, 0);
+        }
+
+        loop {
+            match this.decoder.read(&mut this.buf[..size]) {
+                Ok(0) => return Poll::Ready(None),
+                Ok(n) => return Poll::Ready(Some(Ok(this.buf.split_to(n).freeze()))),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+
+            // more compressed data is needed
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(b))) => this.decoder.get_mut().chunk = b.into(),
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(io::Error::other(e))));
+                }
+                Poll::Ready(None) => this.decoder.get_mut().eof = true,
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "sync")))]
+impl<S> FormData<GzipStream<S>> {
+    /// Creates new `FormData` with boundary, inflates the gzipped request body.
+    #[must_use]
+    pub fn from_gzip_stream(body: S, boundary: &str) -> Self {
+        Self::new(GzipStream::new(body), boundary)
+    }
+}
+
+#[cfg(all(feature = "sync", not(feature = "async")))]
+impl<T: std::io::Read> FormData<flate2::read::MultiGzDecoder<T>> {
+    /// Creates new `FormData` with boundary, inflates the gzipped request body.
+    ///
+    /// All members of a multi-member body are inflated.
+    #[must_use]
+    pub fn from_gzip_stream(body: T, boundary: &str) -> Self {
+        Self::new(flate2::read::MultiGzDecoder::new(body), boundary)
+    }
+}
@@ -160,26 +160,103 @@
 #![warn(missing_docs, unreachable_pub)]
 #![allow(clippy::missing_errors_doc)]
 
+mod boundary;
+pub use boundary::{find_boundary, mime_boundary};
+
+mod builder;
+pub use builder::Builder;
+
+#[cfg(feature = "base64")]
+mod encode;
+#[cfg(feature = "base64")]
+pub use encode::Base64Stream;
+
+#[cfg(feature = "test-util")]
+mod chunked;
+#[cfg(feature = "test-util")]
+pub use chunked::Chunked;
+
+#[cfg(feature = "spool")]
+mod collect;
+#[cfg(feature = "spool")]
+pub use collect::{CollectedFile, Form};
+
+#[cfg(feature = "decrypt")]
+mod decrypt;
+#[cfg(feature = "decrypt")]
+pub use decrypt::{Cipher, Decrypt};
+
 mod error;
-pub use error::Error;
+pub use error::{Error, ErrorContext};
+
+mod event;
+pub use event::{Event, Events};
+
+#[cfg(feature = "checksum")]
+mod checksum;
+#[cfg(feature = "checksum")]
+pub use checksum::Checksum;
 
 mod field;
-pub use field::Field;
+pub use field::{Field, FieldMeta};
 
 mod form;
-pub use form::FormData;
+pub use form::{FormData, ReadMode};
+
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(all(feature = "gzip", feature = "async", not(feature = "sync")))]
+pub use gzip::GzipStream;
+
+#[cfg(feature = "json")]
+mod graphql;
+
+mod line_ending;
+pub use line_ending::LineEnding;
+
+mod lines;
+#[cfg(feature = "json")]
+pub use lines::JsonLines;
+pub use lines::Lines;
 
 mod limits;
 pub use limits::Limits;
 
+#[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+mod rate;
+
+#[cfg(feature = "spool")]
+mod spool;
+#[cfg(feature = "spool")]
+pub use spool::FieldData;
+
+mod snapshot;
+pub use snapshot::StateSnapshot;
+
 mod state;
 pub use state::*;
 
+mod strict;
+pub use strict::Strict;
+
+mod take;
+pub use take::Take;
+
 mod utils;
 
+mod searcher;
+pub use searcher::{BoundarySearcher, MemmemSearcher};
+
+#[cfg(feature = "sniff")]
+mod sniff;
+
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[cfg(all(feature = "async", not(feature = "sync")))]
 mod r#async;
+#[cfg(all(feature = "async", not(feature = "sync")))]
+mod buf_stream;
+#[cfg(all(feature = "async", not(feature = "sync")))]
+pub use buf_stream::BufStream;
 #[cfg(all(feature = "sync", not(feature = "async")))]
 mod sync;
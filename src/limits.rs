@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Various limits on incoming data
@@ -15,10 +17,39 @@ pub struct Limits {
     pub files: Option<usize>,
     /// Max number of parts (fields + files)
     pub parts: Option<usize>,
+    /// Min number of parts (fields + files)
+    pub min_parts: Option<usize>,
     /// Max number of whole stream
     pub stream_size: Option<u64>,
     /// Max number of buffer size
     pub buffer_size: usize,
+    /// Max ratio of a part's header size to its body size
+    pub max_header_body_ratio: Option<f64>,
+    /// Max size of the preamble before the first boundary, `buffer_size` if not set
+    pub max_preamble: Option<usize>,
+    /// Max bytes processed per poll, then yields to the executor
+    pub poll_budget: Option<usize>,
+    /// Max size of field data kept in memory, then spooled to a temp file
+    pub spool_threshold: Option<usize>,
+    /// Allowed content types of file parts, like `image/png` or `image/*`
+    pub content_types: Option<Vec<String>>,
+    /// Allowed content types of file parts by field name, overrides `content_types`
+    pub content_types_for: Option<HashMap<String, Vec<String>>>,
+    /// Strips the leading UTF-8 BOM of text field values
+    #[serde(default)]
+    pub strip_bom: bool,
+    /// Max number of values with the same field name
+    pub max_values_per_name: Option<usize>,
+    /// Skips file parts when collecting text fields, instead of failing
+    #[serde(default)]
+    pub allow_files_ignored: bool,
+    /// Reserved field names, like `__proto__`
+    pub reserved_names: Option<Vec<String>>,
+    /// Max number of headers per part
+    pub max_part_headers: Option<usize>,
+    /// Fails if field data exceeds the declared `Content-Length` of part
+    #[serde(default)]
+    pub enforce_content_length: bool,
 }
 
 impl Default for Limits {
@@ -30,8 +61,21 @@ impl Default for Limits {
             file_size: Some(Self::DEFAULT_FILE_SIZE),
             files: None,
             parts: None,
+            min_parts: None,
             stream_size: Some(Self::DEFAULT_STREAM_SIZE),
             buffer_size: Self::DEFAULT_BUFFER_SIZE,
+            max_header_body_ratio: None,
+            max_preamble: None,
+            poll_budget: None,
+            spool_threshold: None,
+            content_types: None,
+            content_types_for: None,
+            strip_bom: false,
+            max_values_per_name: None,
+            allow_files_ignored: false,
+            reserved_names: None,
+            max_part_headers: None,
+            enforce_content_length: false,
         }
     }
 }
@@ -94,6 +138,13 @@ impl Limits {
         self
     }
 
+    /// Min number of parts (fields + files)
+    #[must_use]
+    pub fn min_parts(mut self, min: usize) -> Self {
+        self.min_parts.replace(min);
+        self
+    }
+
     /// Max number of buffer size
     ///
     /// # Panics
@@ -118,12 +169,120 @@ impl Limits {
         self
     }
 
+    /// Max ratio of a part's header size to its body size, flags the parts
+    /// which carry huge headers and tiny bodies.
+    #[must_use]
+    pub fn max_header_body_ratio(mut self, max: f64) -> Self {
+        self.max_header_body_ratio.replace(max);
+        self
+    }
+
+    /// Max size of the preamble before the first boundary, fails fast the bodies
+    /// which are not multipart. Defaults to `buffer_size`.
+    #[must_use]
+    pub fn max_preamble(mut self, max: usize) -> Self {
+        self.max_preamble.replace(max);
+        self
+    }
+
+    /// Max bytes processed per poll, then the stream wakes itself and returns `Poll::Pending`,
+    /// so other tasks are not starved on a current-thread runtime.
+    #[must_use]
+    pub fn poll_budget(mut self, max: usize) -> Self {
+        self.poll_budget.replace(max);
+        self
+    }
+
+    /// Max size of field data kept in memory, the larger field data is spooled to a temp file.
+    #[must_use]
+    pub fn spool_threshold(mut self, max: usize) -> Self {
+        self.spool_threshold.replace(max);
+        self
+    }
+
+    /// Allowed content types of file parts, allows all by default.
+    #[must_use]
+    pub fn content_types(mut self, types: &[mime::Mime]) -> Self {
+        self.content_types
+            .replace(types.iter().map(|t| t.essence_str().to_string()).collect());
+        self
+    }
+
+    /// Allowed content types of the file parts with `name`, falls back to `content_types`.
+    #[must_use]
+    pub fn content_types_for<N>(mut self, name: N, types: &[mime::Mime]) -> Self
+    where
+        N: Into<String>,
+    {
+        self.content_types_for
+            .get_or_insert_with(HashMap::new)
+            .insert(
+                name.into(),
+                types.iter().map(|t| t.essence_str().to_string()).collect(),
+            );
+        self
+    }
+
+    /// Max number of headers per part, counted before parsing,
+    /// so it applies beyond the fixed slots of the header parser.
+    #[must_use]
+    pub fn max_part_headers(mut self, max: usize) -> Self {
+        self.max_part_headers.replace(max);
+        self
+    }
+
+    /// Fails with `Error::LengthMismatch` if field data exceeds the declared `Content-Length`
+    /// of part, such as a smuggled part, defaults to `false`.
+    #[must_use]
+    pub fn enforce_content_length(mut self, enforce: bool) -> Self {
+        self.enforce_content_length = enforce;
+        self
+    }
+
+    /// Reserved field names, the parts using them are rejected,
+    /// such as `__proto__` against prototype pollution.
+    #[must_use]
+    pub fn reserved_names(mut self, names: &[&str]) -> Self {
+        self.reserved_names
+            .replace(names.iter().map(ToString::to_string).collect());
+        self
+    }
+
+    /// Strips the leading UTF-8 BOM of text field values, such as sent by Windows clients,
+    /// defaults to `false` to keep the exact bytes.
+    #[must_use]
+    pub fn strip_bom(mut self, strip: bool) -> Self {
+        self.strip_bom = strip;
+        self
+    }
+
+    /// Max number of values with the same field name
+    #[must_use]
+    pub fn max_values_per_name(mut self, max: usize) -> Self {
+        self.max_values_per_name.replace(max);
+        self
+    }
+
+    /// Skips file parts in `FormData::collect_text_map`, defaults to `false` to fail with
+    /// `Error::UnexpectedFile`.
+    #[must_use]
+    pub fn allow_files_ignored(mut self, allow: bool) -> Self {
+        self.allow_files_ignored = allow;
+        self
+    }
+
     /// Check parts
     #[must_use]
     pub fn checked_parts(&self, rhs: usize) -> Option<usize> {
         self.parts.filter(|max| rhs > *max)
     }
 
+    /// Check min parts
+    #[must_use]
+    pub fn checked_min_parts(&self, rhs: usize) -> Option<usize> {
+        self.min_parts.filter(|min| rhs < *min)
+    }
+
     /// Check fields
     #[must_use]
     pub fn checked_fields(&self, rhs: usize) -> Option<usize> {
@@ -154,6 +313,69 @@ impl Limits {
         self.field_size.filter(|max| rhs > *max)
     }
 
+    /// Check header to body ratio, skips empty bodies
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn checked_header_body_ratio(&self, header: usize, body: usize) -> Option<f64> {
+        self.max_header_body_ratio
+            .filter(|max| body > 0 && header as f64 > max * body as f64)
+    }
+
+    /// Check preamble size, `buffer_size` is used if `max_preamble` is not set
+    #[must_use]
+    pub fn checked_preamble(&self, rhs: usize) -> Option<usize> {
+        Some(self.max_preamble.unwrap_or(self.buffer_size)).filter(|max| rhs > *max)
+    }
+
+    /// Check poll budget
+    #[must_use]
+    pub fn checked_poll_budget(&self, rhs: usize) -> Option<usize> {
+        self.poll_budget.filter(|max| rhs >= *max)
+    }
+
+    /// Check spool threshold
+    #[must_use]
+    pub fn checked_spool_threshold(&self, rhs: usize) -> Option<usize> {
+        self.spool_threshold.filter(|max| rhs > *max)
+    }
+
+    /// Check content type of the file part, returns the allow-list which rejects it
+    #[must_use]
+    pub fn checked_content_type(&self, name: &str, content_type: &mime::Mime) -> Option<&[String]> {
+        let types = self
+            .content_types_for
+            .as_ref()
+            .and_then(|m| m.get(name))
+            .or(self.content_types.as_ref())?;
+
+        let allowed = types.iter().any(|t| match t.strip_suffix("/*") {
+            Some(ty) => ty == content_type.type_(),
+            None => t == content_type.essence_str(),
+        });
+
+        (!allowed).then_some(types)
+    }
+
+    /// Check values with the same field name
+    #[must_use]
+    pub fn checked_values_per_name(&self, rhs: usize) -> Option<usize> {
+        self.max_values_per_name.filter(|max| rhs > *max)
+    }
+
+    /// Check part headers
+    #[must_use]
+    pub fn checked_part_headers(&self, rhs: usize) -> Option<usize> {
+        self.max_part_headers.filter(|max| rhs > *max)
+    }
+
+    /// Check reserved field name
+    #[must_use]
+    pub fn checked_reserved_name(&self, name: &str) -> bool {
+        self.reserved_names
+            .as_ref()
+            .is_some_and(|names| names.iter().any(|n| n == name))
+    }
+
     /// Check field name size
     #[must_use]
     pub fn checked_field_name_size(&self, rhs: usize) -> Option<usize> {
@@ -0,0 +1,56 @@
+/// Line ending of the normalized text, see `Field::copy_to_file_normalized`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Converts `\r\n` to `\n`.
+    Lf,
+    /// Converts bare `\n` to `\r\n`.
+    Crlf,
+}
+
+/// Normalizes line endings of chunks, a `\r\n` may be split across chunks.
+#[derive(Debug)]
+pub(crate) struct Normalizer {
+    mode: LineEnding,
+    /// The last byte is `\r`.
+    cr: bool,
+}
+
+impl Normalizer {
+    pub(crate) fn new(mode: LineEnding) -> Self {
+        Self { mode, cr: false }
+    }
+
+    /// Normalizes a chunk into `out`, a trailing `\r` is held in `Lf` mode.
+    pub(crate) fn normalize(&mut self, buf: &[u8], out: &mut Vec<u8>) {
+        out.reserve(buf.len());
+
+        for &b in buf {
+            match self.mode {
+                LineEnding::Lf => {
+                    if self.cr && b != b'\n' {
+                        out.push(b'\r');
+                    }
+                    self.cr = b == b'\r';
+                    if !self.cr {
+                        out.push(b);
+                    }
+                }
+                LineEnding::Crlf => {
+                    if b == b'\n' && !self.cr {
+                        out.push(b'\r');
+                    }
+                    self.cr = b == b'\r';
+                    out.push(b);
+                }
+            }
+        }
+    }
+
+    /// Flushes the held `\r`.
+    pub(crate) fn finish(&mut self, out: &mut Vec<u8>) {
+        if self.mode == LineEnding::Lf && self.cr {
+            out.push(b'\r');
+        }
+        self.cr = false;
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+#[cfg(feature = "json")]
+use std::marker::PhantomData;
+
+use bytes::{Bytes, BytesMut};
+use memchr::memchr;
+
+use crate::Field;
+
+/// Lines of field data, splits on `\n` and strips the trailing `\r`.
+pub struct Lines<'a, T> {
+    pub(crate) field: &'a mut Field<T>,
+    pub(crate) buffer: BytesMut,
+    pub(crate) eof: bool,
+}
+
+impl<T> Lines<'_, T> {
+    /// Takes a line from buffer.
+    pub(crate) fn split_line(&mut self) -> Option<Bytes> {
+        let line = match memchr(b'\n', &self.buffer) {
+            Some(n) => {
+                let mut line = self.buffer.split_to(n + 1);
+                line.truncate(n);
+                line
+            }
+            None if self.eof && !self.buffer.is_empty() => self.buffer.split(),
+            None => return None,
+        };
+
+        let mut line = line.freeze();
+        if line.last() == Some(&b'\r') {
+            line.truncate(line.len() - 1);
+        }
+        Some(line)
+    }
+}
+
+impl<T> fmt::Debug for Lines<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lines")
+            .field("field", &self.field)
+            .field("buffered", &self.buffer.len())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+/// JSON values of field data, each line is parsed as a JSON value, blank lines are skipped.
+#[cfg(feature = "json")]
+pub struct JsonLines<'a, T, D> {
+    pub(crate) lines: Lines<'a, T>,
+    pub(crate) fail_fast: bool,
+    pub(crate) failed: bool,
+    pub(crate) marker: PhantomData<fn() -> D>,
+}
+
+#[cfg(feature = "json")]
+impl<T, D> JsonLines<'_, T, D> {
+    /// Stops at the first invalid line, defaults to `false`,
+    /// an invalid line is yielded as an error and the following lines are still parsed.
+    #[must_use]
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T, D> fmt::Debug for JsonLines<'_, T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLines")
+            .field("lines", &self.lines)
+            .field("fail_fast", &self.fail_fast)
+            .field("failed", &self.failed)
+            .finish()
+    }
+}
+
+impl<T> Field<T> {
+    /// Reads field data line by line.
+    pub fn lines(&mut self) -> Lines<'_, T> {
+        Lines {
+            field: self,
+            buffer: BytesMut::new(),
+            eof: false,
+        }
+    }
+
+    /// Reads field data as newline-delimited JSON.
+    #[cfg(feature = "json")]
+    pub fn json_lines<D>(&mut self) -> JsonLines<'_, T, D>
+    where
+        D: serde::de::DeserializeOwned,
+    {
+        JsonLines {
+            lines: self.lines(),
+            fail_fast: false,
+            failed: false,
+            marker: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+#[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use crate::State;
+
+/// Paces the reads of the whole stream, in bytes per second.
+pub(crate) struct RateLimit {
+    bytes_per_sec: u64,
+    started: Option<Instant>,
+    #[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+    sleep: Option<Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl RateLimit {
+    /// Creates new `RateLimit`, the clock starts at the first read.
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        assert!(bytes_per_sec > 0, "rate limit must be greater than zero");
+
+        Self {
+            bytes_per_sec,
+            started: None,
+            #[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+            sleep: None,
+        }
+    }
+
+    /// Gets the delay before reading more, when `length` bytes have been read.
+    pub(crate) fn delay(&mut self, length: u64) -> Option<Duration> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let nanos = u128::from(length) * 1_000_000_000 / u128::from(self.bytes_per_sec);
+        let due = Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX));
+
+        due.checked_sub(started.elapsed())
+            .filter(|delay| !delay.is_zero())
+    }
+
+    /// Polls the delay before reading more.
+    #[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+    pub(crate) fn poll_delay(&mut self, cx: &mut Context<'_>, length: u64) -> Poll<()> {
+        if self.sleep.is_none() {
+            match self.delay(length) {
+                Some(delay) => self.sleep = Some(Box::pin(tokio::time::sleep(delay))),
+                None => return Poll::Ready(()),
+            }
+        }
+
+        if let Some(sleep) = self.sleep.as_mut() {
+            ready!(sleep.as_mut().poll(cx));
+            self.sleep = None;
+        }
+
+        Poll::Ready(())
+    }
+
+    /// Blocks the delay before reading more.
+    #[cfg(all(feature = "sync", not(feature = "async")))]
+    pub(crate) fn wait(&mut self, length: u64) {
+        if let Some(delay) = self.delay(length) {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+impl<T> State<T> {
+    /// Polls the delay of rate limit before reading the stream.
+    #[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+    pub(crate) fn poll_rate_limit(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let length = self.length;
+        match self.rate_limit.as_mut() {
+            Some(rate_limit) => rate_limit.poll_delay(cx, length),
+            None => Poll::Ready(()),
+        }
+    }
+
+    /// Blocks the delay of rate limit before reading the stream.
+    #[cfg(all(feature = "sync", not(feature = "async")))]
+    pub(crate) fn wait_rate_limit(&mut self) {
+        let length = self.length;
+        if let Some(rate_limit) = self.rate_limit.as_mut() {
+            rate_limit.wait(length);
+        }
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("bytes_per_sec", &self.bytes_per_sec)
+            .field("started", &self.started)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,17 @@
+use memchr::memmem;
+
+/// Searches the boundary delimiter in buffer, such as a SIMD scanner.
+pub trait BoundarySearcher: Send + Sync {
+    /// Finds the first position of `needle` in `haystack`.
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize>;
+}
+
+/// The default searcher by [`memchr::memmem`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemmemSearcher;
+
+impl BoundarySearcher for MemmemSearcher {
+    fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        memmem::find(haystack, needle)
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bytes::{Bytes, BytesMut};
+use http::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Field, Flag, FormData, Limits, ReadMode, Result, State, Strict};
+
+/// Serializable position of the parser, see `State::snapshot` and `Field::snapshot`.
+///
+/// The error context, hooks, searcher and GraphQL mode are not kept, sets them again after restoring.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    boundary: Vec<u8>,
+    flag: Flag,
+    eof: bool,
+    is_readable: bool,
+    length: u64,
+    buffer: Vec<u8>,
+    total: usize,
+    files: usize,
+    fields: usize,
+    limits: Limits,
+    strict: Strict,
+    read_mode: ReadMode,
+    active: Option<bool>,
+    buffered: HashMap<usize, Vec<u8>>,
+    content_length: Option<u64>,
+    part_length: u64,
+    #[serde(default)]
+    part_header_size: usize,
+    tempdir: Option<PathBuf>,
+    #[serde(default)]
+    byteranges: bool,
+    field: Option<FieldSnapshot>,
+}
+
+/// The active field of snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FieldSnapshot {
+    index: usize,
+    length: usize,
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    headers: Vec<(String, Vec<u8>)>,
+    peeked: Option<Vec<u8>>,
+    header_size: usize,
+    #[serde(default)]
+    raw_content_disposition: Option<Vec<u8>>,
+    #[serde(default)]
+    body_start: Option<u64>,
+}
+
+impl StateSnapshot {
+    /// Gets the size of data has been read from the source,
+    /// the restored state resumes from this position.
+    #[must_use]
+    pub fn offset(&self) -> u64 {
+        self.length
+    }
+}
+
+impl<T> State<T> {
+    /// Takes a snapshot of the parser position, the buffered data is included.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            boundary: self.boundary().to_vec(),
+            flag: self.flag.clone(),
+            eof: self.eof,
+            is_readable: self.is_readable,
+            length: self.length,
+            buffer: self.buffer.to_vec(),
+            total: self.total,
+            files: self.files,
+            fields: self.fields,
+            limits: self.limits.clone(),
+            strict: self.strict,
+            read_mode: self.read_mode,
+            active: self.active,
+            buffered: self
+                .buffered
+                .iter()
+                .map(|(index, rest)| (*index, rest.to_vec()))
+                .collect(),
+            content_length: self.content_length,
+            part_length: self.part_length,
+            part_header_size: self.part_header_size,
+            tempdir: self.tempdir.clone(),
+            byteranges: self.byteranges,
+            field: None,
+        }
+    }
+
+    /// Restores the state from snapshot, `io` must resume from `StateSnapshot::offset`.
+    pub fn restore(snapshot: StateSnapshot, io: T) -> Self {
+        let mut state = State::new(io, &snapshot.boundary, snapshot.limits);
+
+        state.flag = snapshot.flag;
+        state.eof = snapshot.eof;
+        state.is_readable = snapshot.is_readable;
+        state.length = snapshot.length;
+        state.buffer.clear();
+        state.buffer.extend_from_slice(&snapshot.buffer);
+        state.total = snapshot.total;
+        state.files = snapshot.files;
+        state.fields = snapshot.fields;
+        state.strict = snapshot.strict;
+        state.read_mode = snapshot.read_mode;
+        state.active = snapshot.active;
+        state.buffered = snapshot
+            .buffered
+            .into_iter()
+            .map(|(index, rest)| (index, BytesMut::from(&rest[..])))
+            .collect();
+        state.content_length = snapshot.content_length;
+        state.part_length = snapshot.part_length;
+        state.part_header_size = snapshot.part_header_size;
+        state.tempdir = snapshot.tempdir;
+        state.byteranges = snapshot.byteranges;
+
+        state
+    }
+}
+
+impl<T> Field<T> {
+    /// Takes a snapshot of the parser position with this field,
+    /// so the field can be resumed by `FormData::restore`, fails if Field is consumed.
+    pub fn snapshot(&self) -> Result<StateSnapshot> {
+        let state = self.state.as_ref().ok_or(Error::FieldConsumed)?;
+        let mut snapshot = state.try_lock().map_err(Error::from)?.snapshot();
+
+        snapshot.field.replace(FieldSnapshot {
+            index: self.index,
+            length: self.length,
+            name: self.name.clone(),
+            filename: self.filename.clone(),
+            content_type: self.content_type.as_ref().map(ToString::to_string),
+            headers: self
+                .headers
+                .iter()
+                .flatten()
+                .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+                .collect(),
+            peeked: self.peeked.as_ref().map(|peeked| peeked.to_vec()),
+            header_size: self.header_size,
+            raw_content_disposition: self
+                .raw_content_disposition
+                .as_ref()
+                .map(|value| value.as_bytes().to_vec()),
+            body_start: self.body_start,
+        });
+
+        Ok(snapshot)
+    }
+}
+
+impl<T> FormData<T> {
+    /// Restores `FormData` from snapshot, `io` must resume from `StateSnapshot::offset`.
+    ///
+    /// Returns the resumed field too, if the snapshot is taken by `Field::snapshot`.
+    pub fn restore(mut snapshot: StateSnapshot, io: T) -> Result<(Self, Option<Field<T>>)> {
+        let field = snapshot.field.take();
+        let form = Self {
+            state: Arc::new(Mutex::new(State::restore(snapshot, io))),
+        };
+
+        let Some(snapshot) = field else {
+            return Ok((form, None));
+        };
+
+        let mut headers = HeaderMap::with_capacity(snapshot.headers.len());
+        for (name, value) in snapshot.headers {
+            headers.append(
+                HeaderName::try_from(name).map_err(|_| Error::InvalidHeader)?,
+                HeaderValue::try_from(value).map_err(|_| Error::InvalidHeader)?,
+            );
+        }
+
+        let mut field = Field::empty();
+        field.index = snapshot.index;
+        field.length = snapshot.length;
+        field.name = snapshot.name;
+        field.filename = snapshot.filename;
+        field.content_type = snapshot.content_type.and_then(|m| m.parse().ok());
+        field.peeked = snapshot.peeked.map(Bytes::from);
+        field.header_size = snapshot.header_size;
+        field.raw_content_disposition = snapshot
+            .raw_content_disposition
+            .map(HeaderValue::try_from)
+            .transpose()
+            .map_err(|_| Error::InvalidHeader)?;
+        field.body_start = snapshot.body_start;
+        if !headers.is_empty() {
+            field.headers.replace(headers);
+        }
+        field.state.replace(form.state());
+        field.init_span();
+
+        Ok((form, Some(field)))
+    }
+}
@@ -0,0 +1,65 @@
+use mime::Mime;
+
+/// The max length of magic numbers, including the offset.
+pub(crate) const MAX_MAGIC_LEN: usize = 12;
+
+/// Magic numbers: `(offset, bytes, essence)`
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xFF\xD8\xFF", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"II*\x00", "image/tiff"),
+    (0, b"MM\x00*", "image/tiff"),
+    (0, b"\x00\x00\x01\x00", "image/x-icon"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"%!PS", "application/postscript"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1F\x8B", "application/gzip"),
+    (0, b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+    (0, b"Rar!\x1A\x07", "application/vnd.rar"),
+    (0, b"\x00asm", "application/wasm"),
+    (0, b"\x7FELF", "application/x-executable"),
+    (0, b"MZ", "application/x-msdownload"),
+    (0, b"<?php", "application/x-httpd-php"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"ID3", "audio/mpeg"),
+    (8, b"WAVE", "audio/wav"),
+    (8, b"AVI ", "video/x-msvideo"),
+    (0, b"wOFF", "font/woff"),
+    (0, b"wOF2", "font/woff2"),
+];
+
+/// Sniffs the essence of mime type by the magic numbers.
+pub(crate) fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| {
+            bytes
+                .get(*offset..offset + magic.len())
+                .is_some_and(|b| b == *magic)
+        })
+        .map(|(_, _, essence)| *essence)
+}
+
+/// Verifies the declared mime type with the sniffed one.
+///
+/// An absent or `application/octet-stream` declared type agrees with any data,
+/// a declared type which has magic numbers must be matched by data.
+pub(crate) fn verify(declared: Option<&Mime>, bytes: &[u8]) -> bool {
+    let Some(declared) = declared
+        .map(Mime::essence_str)
+        .filter(|essence| *essence != mime::APPLICATION_OCTET_STREAM.essence_str())
+    else {
+        return true;
+    };
+
+    match sniff(bytes) {
+        Some(sniffed) => sniffed.eq_ignore_ascii_case(declared),
+        None => !SIGNATURES
+            .iter()
+            .any(|(_, _, essence)| essence.eq_ignore_ascii_case(declared)),
+    }
+}
@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use tempfile::NamedTempFile;
+
+use crate::{Error, Field, Result};
+
+/// Field data, in memory or spooled to a temp file.
+#[derive(Debug)]
+pub enum FieldData {
+    /// Small field data, not over `Limits::spool_threshold`.
+    Memory(Bytes),
+    /// Large field data, spooled to a temp file.
+    File(NamedTempFile),
+}
+
+impl<T> Field<T> {
+    /// Gets the spool threshold and the temp dir from the state.
+    pub(crate) fn spool_options(&self) -> Result<(Option<usize>, Option<std::path::PathBuf>)> {
+        let state = self
+            .state
+            .as_ref()
+            .ok_or(Error::FieldConsumed)?
+            .try_lock()
+            .map_err(Error::from)?;
+        Ok((state.limits.spool_threshold, state.tempdir.clone()))
+    }
+}
+
+/// Creates a temp file in `dir`, or in the default temp dir.
+pub(crate) fn create_spool_file(dir: Option<&std::path::Path>) -> Result<NamedTempFile> {
+    Ok(match dir {
+        Some(dir) => NamedTempFile::new_in(dir)?,
+        None => NamedTempFile::new()?,
+    })
+}
@@ -1,17 +1,32 @@
-use std::fmt;
+use std::{collections::HashMap, fmt, path::PathBuf, sync::Arc, task::Poll};
 
 #[cfg(feature = "async")]
 use std::task::Waker;
 
 use bytes::{Buf, Bytes, BytesMut};
 use memchr::memmem;
+use serde::{Deserialize, Serialize};
+use tracing::trace;
+
+#[cfg(feature = "json")]
+use crate::graphql::GraphQl;
+#[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+use crate::rate::RateLimit;
+
+use http::{header::CONTENT_LENGTH, HeaderMap, HeaderValue};
 
 use crate::{
-    utils::{CRLF, CRLFS, DASHES},
-    Limits,
+    utils::{parse_content_disposition, parse_part_headers, CRLF, CRLFS, DASHES},
+    BoundarySearcher, Error, ErrorContext, FieldMeta, Limits, MemmemSearcher, ReadMode, Strict,
 };
 
-#[derive(Debug, PartialEq)]
+/// Maps the errors produced by the streams.
+pub(crate) type ErrorMapper = Arc<dyn Fn(Error) -> Error + Send + Sync>;
+
+/// Runs when a field is fully consumed, with the final length.
+pub(crate) type FieldHook = Box<dyn FnMut(&FieldMeta, u64) + Send>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) enum Flag {
     Delimiting(bool),
     Heading(usize),
@@ -36,6 +51,37 @@ pub struct State<T> {
     pub(crate) files: usize,
     pub(crate) fields: usize,
     pub(crate) limits: Limits,
+    pub(crate) error_mapper: Option<ErrorMapper>,
+    /// The last error produced by the stream.
+    pub(crate) last_error: Option<ErrorContext>,
+    pub(crate) strict: Strict,
+    pub(crate) on_field_complete: Option<FieldHook>,
+    pub(crate) read_mode: ReadMode,
+    /// The active field is a file or not.
+    pub(crate) active: Option<bool>,
+    pub(crate) draining: BytesMut,
+    /// The rest data of drained fields, by index.
+    pub(crate) buffered: HashMap<usize, BytesMut>,
+    /// The declared `Content-Length` of the current part.
+    pub(crate) content_length: Option<u64>,
+    /// The decoded data size of the current part.
+    pub(crate) part_length: u64,
+    /// The header size of the current part, it is taken when the part is ended.
+    pub(crate) part_header_size: usize,
+    /// The error of the ended part, taken by its consumer.
+    part_error: Option<Error>,
+    /// The dir of spooled temp files.
+    pub(crate) tempdir: Option<PathBuf>,
+    pub(crate) searcher: Box<dyn BoundarySearcher>,
+    #[cfg(feature = "json")]
+    pub(crate) graphql: Option<GraphQl>,
+    /// Parses `multipart/byteranges`, the `Content-Disposition` is optional.
+    pub(crate) byteranges: bool,
+    /// Paces the reads of the whole stream.
+    #[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+    pub(crate) rate_limit: Option<RateLimit>,
+    #[cfg(debug_assertions)]
+    realloc_count: usize,
 }
 
 impl<T> State<T> {
@@ -54,6 +100,27 @@ impl<T> State<T> {
         Self {
             io,
             limits,
+            error_mapper: None,
+            last_error: None,
+            strict: Strict::default(),
+            on_field_complete: None,
+            read_mode: ReadMode::default(),
+            active: None,
+            draining: BytesMut::new(),
+            buffered: HashMap::new(),
+            content_length: None,
+            part_length: 0,
+            part_header_size: 0,
+            part_error: None,
+            tempdir: None,
+            searcher: Box::new(MemmemSearcher),
+            #[cfg(feature = "json")]
+            graphql: None,
+            byteranges: false,
+            #[cfg(any(feature = "tokio", all(feature = "sync", not(feature = "async"))))]
+            rate_limit: None,
+            #[cfg(debug_assertions)]
+            realloc_count: 0,
             total: 0,
             files: 0,
             fields: 0,
@@ -92,6 +159,59 @@ impl<T> State<T> {
         &mut self.limits
     }
 
+    /// Maps the error with the error context.
+    pub fn map_error(&self, e: Error) -> Error {
+        match &self.error_mapper {
+            Some(f) => f(e),
+            None => e,
+        }
+    }
+
+    /// Maps the error, then records it as the last error with the progress.
+    pub(crate) fn record_error(&mut self, e: Error) -> Error {
+        let e = self.map_error(e);
+        self.last_error.replace(ErrorContext {
+            error: Arc::new(e.duplicate()),
+            total: self.total,
+            files: self.files,
+            fields: self.fields,
+            length: self.length,
+        });
+        e
+    }
+
+    /// Counts the capacity growths of buffer, only in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn realloc_count(&self) -> usize {
+        self.realloc_count
+    }
+
+    /// Reserves capacity of buffer.
+    pub(crate) fn reserve_buffer(&mut self, additional: usize) {
+        #[cfg(debug_assertions)]
+        let capacity = self.buffer.capacity();
+
+        self.buffer.reserve(additional);
+
+        #[cfg(debug_assertions)]
+        if self.buffer.capacity() > capacity {
+            self.realloc_count += 1;
+        }
+    }
+
+    /// Extends buffer.
+    pub(crate) fn extend_buffer(&mut self, bytes: &[u8]) {
+        #[cfg(debug_assertions)]
+        let capacity = self.buffer.capacity();
+
+        self.buffer.extend_from_slice(bytes);
+
+        #[cfg(debug_assertions)]
+        if self.buffer.capacity() > capacity {
+            self.realloc_count += 1;
+        }
+    }
+
     /// Splits buffer.
     pub fn split_buffer(&mut self, n: usize) -> Bytes {
         self.buffer.split_to(n).freeze()
@@ -129,20 +249,277 @@ impl<T> State<T> {
         &self.delimiter[4..]
     }
 
+    /// Gets the offset of the buffered data in the whole stream.
+    ///
+    /// The synthetic `\r\n` is counted before the first delimiter is consumed.
+    pub(crate) fn offset(&self) -> u64 {
+        self.length.saturating_sub(self.buffer.len() as u64)
+    }
+
+    /// Starts a new part with the declared `Content-Length` of headers,
+    /// returns the offset of its data in the whole stream.
+    pub(crate) fn part_started(&mut self, headers: &HeaderMap, header_size: usize) -> u64 {
+        self.content_length = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        self.part_length = 0;
+        self.part_header_size = header_size;
+        self.offset()
+    }
+
+    /// Checks the part which is ended, however it is consumed.
+    ///
+    /// The failure is kept until it is taken by `take_part_error`.
+    fn part_ended(&mut self) {
+        let header_size = std::mem::take(&mut self.part_header_size);
+
+        // suspicious part
+        if let Some(max) = self
+            .limits
+            .checked_header_body_ratio(header_size, self.part_size())
+        {
+            self.part_error.replace(Error::SuspiciousPart(max));
+        }
+    }
+
+    /// Takes the error of the ended part.
+    pub(crate) fn take_part_error(&mut self) -> Result<(), Error> {
+        self.part_error.take().map_or(Ok(()), Err)
+    }
+
+    /// Counts `l` bytes read from the stream, checks the stream size limit.
+    ///
+    /// Fails with `Error::PayloadTooLarge(u64::MAX)` if the length overflows.
+    pub(crate) fn count_stream_size(&mut self, l: u64) -> Result<(), Error> {
+        let length = self
+            .length
+            .checked_add(l)
+            .ok_or(Error::PayloadTooLarge(u64::MAX))?;
+
+        if let Some(max) = self.limits.checked_stream_size(length) {
+            return Err(Error::PayloadTooLarge(max));
+        }
+
+        self.length = length;
+
+        Ok(())
+    }
+
+    /// Parses the headers of part, checks the number of headers and the strict rules.
+    pub(crate) fn parse_part_headers(&self, buf: &[u8]) -> Result<HeaderMap, Error> {
+        // the headers end with an empty line
+        let count = memmem::find_iter(buf, &CRLF).count().saturating_sub(1);
+        if let Some(limit) = self.limits.checked_part_headers(count) {
+            return Err(Error::TooManyPartHeaders { limit });
+        }
+
+        let headers = parse_part_headers(buf)?;
+
+        // strict checks
+        self.strict.check(&headers)?;
+
+        Ok(headers)
+    }
+
+    /// Parses the name and filename of part, the `Content-Disposition` is optional in
+    /// byteranges mode.
+    pub(crate) fn parse_content_disposition(
+        &self,
+        disposition: Option<&HeaderValue>,
+    ) -> Result<(String, Option<String>), Error> {
+        match disposition {
+            None if self.byteranges => Ok((String::new(), None)),
+            disposition => disposition
+                .map(HeaderValue::as_bytes)
+                .map(parse_content_disposition)
+                .and_then(Result::ok)
+                .ok_or(Error::InvalidContentDisposition),
+        }
+    }
+
+    /// Checks the field name of the part.
+    pub(crate) fn check_field_name(&self, name: &str) -> Result<(), Error> {
+        // field name is too long
+        if let Some(max) = self.limits.checked_field_name_size(name.len()) {
+            return Err(Error::FieldNameTooLong(max));
+        }
+
+        // field name is reserved
+        if self.limits.checked_reserved_name(name) {
+            return Err(Error::ReservedFieldName(name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the size limits of the active field.
+    pub(crate) fn check_part_size(&self, is_file: bool, len: usize) -> Result<(), Error> {
+        if is_file {
+            if let Some(max) = self.limits.checked_file_size(len) {
+                return Err(Error::FileTooLarge(max));
+            }
+        } else if let Some(max) = self.limits.checked_field_size(len) {
+            return Err(Error::FieldTooLarge(max));
+        }
+
+        Ok(())
+    }
+
+    /// Buffers the drained data of the active field, checks the size limits.
+    pub(crate) fn drain(&mut self, is_file: bool, buf: &[u8]) -> Result<(), Error> {
+        self.check_part_size(is_file, self.draining.len() + buf.len())?;
+        self.draining.extend_from_slice(buf);
+
+        Ok(())
+    }
+
+    /// Gets the decoded data size of the current part.
+    pub(crate) fn part_size(&self) -> usize {
+        usize::try_from(self.part_length).unwrap_or(usize::MAX)
+    }
+
+    /// The active field is drained, its rest data can be read later.
+    pub(crate) fn drained(&mut self) {
+        let rest = self.draining.split();
+        self.buffered.insert(self.total - 1, rest);
+        self.active = None;
+    }
+
+    /// Checks the body is multipart before the first delimiter is found.
+    ///
+    /// Fails if the stream is ended, or the preamble doesn't begin with `--`
+    /// and is over `max_preamble`, which defaults to `buffer_size`.
+    pub(crate) fn check_multipart(&self) -> Result<(), Error> {
+        if self.total > 0 || Flag::Delimiting(false) != self.flag {
+            return Ok(());
+        }
+
+        if self.eof {
+            return Err(Error::NotMultipart);
+        }
+
+        // skips the synthetic `\r\n`
+        let preamble = self.buffer.get(CRLF.len()..).unwrap_or_default();
+        let trimmed = preamble.trim_ascii_start();
+
+        if trimmed.len() >= DASHES.len()
+            && !trimmed.starts_with(&DASHES)
+            && self.limits.checked_preamble(preamble.len()).is_some()
+        {
+            return Err(Error::NotMultipart);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the stream is not ended, when the body can't be decoded any further.
+    ///
+    /// The headers of part are truncated if the ending `\r\n\r\n` is not found.
+    pub(crate) fn check_eof(&self) -> Result<(), Error> {
+        if self.eof {
+            return Err(if Flag::Header == self.flag {
+                Error::IncompleteHeaders
+            } else {
+                Error::IncompleteStream
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Ends the final part which is empty, but the closing boundary is missing,
+    /// same as the closed one.
+    ///
+    /// Only the trailing `\r\n` is allowed after the headers.
+    fn decode_empty_final_part(&mut self) -> bool {
+        if self.strict.closing_boundary || self.part_length > 0 {
+            return false;
+        }
+
+        if !self.buffer.is_empty() && self.buffer[..] != CRLF {
+            return false;
+        }
+
+        trace!("final part is empty");
+        self.buffer.clear();
+        self.flag = Flag::Eof;
+        true
+    }
+
+    /// Takes the final part which is sized by `Content-Length`, but the closing boundary is missing.
+    ///
+    /// Only the trailing `\r\n` is allowed after the declared data.
+    fn decode_sized_final_part(&mut self) -> Option<Bytes> {
+        if self.strict.closing_boundary {
+            return None;
+        }
+
+        let rest = self.content_length?.checked_sub(self.part_length)?;
+        let n = usize::try_from(rest).ok()?;
+        let tail = self.buffer.get(n..)?;
+        if !tail.is_empty() && tail != CRLF {
+            return None;
+        }
+
+        trace!("final part is sized by content-length");
+        let buf = self.buffer.split_to(n).freeze();
+        self.buffer.clear();
+        self.part_length += rest;
+        self.flag = Flag::Eof;
+        Some(buf)
+    }
+
+    /// Decodes the buffered data of the current part, never reads the stream.
+    ///
+    /// Returns `Poll::Pending` if more data is needed.
+    pub(crate) fn poll_buffered(&mut self) -> Poll<Option<Bytes>> {
+        if !self.is_readable {
+            return Poll::Pending;
+        }
+
+        trace!("attempting to decode a part");
+
+        // field
+        if let Some(data) = self.decode() {
+            trace!(bytes = data.len(), "part decoded from buffer");
+            return Poll::Ready(Some(data));
+        }
+
+        // field stream is ended
+        if Flag::Next == self.flag {
+            self.part_ended();
+            return Poll::Ready(None);
+        }
+
+        // whole stream is ended
+        if Flag::Eof == self.flag {
+            self.part_ended();
+            self.length = self.length.saturating_sub(self.buffer.len() as u64);
+            self.buffer.clear();
+            self.eof = true;
+            return Poll::Ready(None);
+        }
+
+        Poll::Pending
+    }
+
     pub(crate) fn decode(&mut self) -> Option<Bytes> {
         if let Flag::Delimiting(boding) = self.flag {
-            if let Some(n) = memmem::find(&self.buffer, &self.delimiter) {
+            if let Some(n) = self.searcher.find(&self.buffer, &self.delimiter) {
                 self.flag = Flag::Heading(n);
             } else {
                 // Empty Request Body
-                if self.eof && self.buffer.len() == 2 && self.buffer[..2] == CRLF {
+                if !boding && self.eof && self.buffer.len() == 2 && self.buffer[..2] == CRLF {
                     self.buffer.advance(2);
                     self.flag = Flag::Eof;
                     return None;
                 }
 
-                // Empty Part Body
-                if memmem::find(&self.buffer, &self.delimiter[2..]).is_some() {
+                // Empty Part Body, the `\r\n` is consumed by headers,
+                // a `--boundary` after a bare `\r` in part body is data.
+                if boding && self.part_length == 0 && self.buffer.starts_with(&self.delimiter[2..])
+                {
                     self.flag = Flag::Next;
                     self.buffer.advance(self.delimiter.len() - 2);
                     return None;
@@ -152,8 +529,19 @@ impl<T> State<T> {
                 if boding {
                     // Returns buffer with `max_buf_size`
                     if self.limits.buffer_size + self.delimiter.len() < self.buffer.len() {
+                        self.part_length += self.limits.buffer_size as u64;
                         return Some(self.buffer.split_to(self.limits.buffer_size).freeze());
                     }
+
+                    // Final Part without closing boundary
+                    if self.eof {
+                        if self.decode_empty_final_part() {
+                            return None;
+                        }
+                        if let Some(buf) = self.decode_sized_final_part() {
+                            return (!buf.is_empty()).then_some(buf);
+                        }
+                    }
                 }
             }
         }
@@ -178,6 +566,7 @@ impl<T> State<T> {
                 // prev part last data
                 let buf = self.buffer.split_to(*n).freeze();
                 *n = 0;
+                self.part_length += buf.len() as u64;
                 return Some(buf);
             }
         }
@@ -196,7 +585,9 @@ impl<T> State<T> {
                 return None;
             } else {
                 // We dont parse other format, like `\n`
-                self.length -= (self.delimiter.len() - 2) as u64;
+                self.length = self
+                    .length
+                    .saturating_sub((self.delimiter.len() - 2) as u64);
                 self.flag = Flag::Eof;
                 return None;
             }
@@ -224,6 +615,7 @@ impl<T> fmt::Debug for State<T> {
             .field("length", &self.length)
             .field("limits", &self.limits)
             .field("is_readable", &self.is_readable)
+            .field("read_mode", &self.read_mode)
             .field("boundary", &String::from_utf8_lossy(self.boundary()))
             .finish_non_exhaustive()
     }
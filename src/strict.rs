@@ -0,0 +1,115 @@
+use http::header::{HeaderMap, HeaderName, CONTENT_DISPOSITION};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+const CONTENT_TRANSFER_ENCODING: HeaderName = HeaderName::from_static("content-transfer-encoding");
+
+/// The allowed values of `Content-Transfer-Encoding`, see [rfc7578 section 4.7].
+///
+/// [rfc7578 section 4.7]: <https://tools.ietf.org/html/rfc7578#section-4.7>
+const TRANSFER_ENCODINGS: [&str; 3] = ["7bit", "8bit", "binary"];
+
+/// Strict checks on the headers of parts, all disabled by default.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Strict {
+    /// The disposition type must be exactly `form-data`.
+    pub disposition: bool,
+    /// The `name` parameter is required.
+    pub name: bool,
+    /// At most one `filename` parameter.
+    pub filename: bool,
+    /// The `Content-Transfer-Encoding` is restricted to `7bit`, `8bit` and `binary`.
+    pub transfer_encoding: bool,
+    /// The closing boundary is required, even if the final part is sized by `Content-Length`.
+    pub closing_boundary: bool,
+}
+
+impl Strict {
+    /// Enables all checks of [rfc7578].
+    ///
+    /// [rfc7578]: <https://tools.ietf.org/html/rfc7578>
+    #[must_use]
+    pub fn rfc7578() -> Self {
+        Self {
+            disposition: true,
+            name: true,
+            filename: true,
+            transfer_encoding: true,
+            closing_boundary: true,
+        }
+    }
+
+    /// Checks the headers of a part.
+    pub fn check(&self, headers: &HeaderMap) -> Result<()> {
+        if let Some(value) = headers.get(CONTENT_DISPOSITION) {
+            let mut params = split_params(value.as_bytes());
+
+            // disposition type
+            if self.disposition
+                && !params
+                    .next()
+                    .is_some_and(|t| t.eq_ignore_ascii_case(b"form-data"))
+            {
+                return Err(Error::InvalidDispositionType);
+            }
+
+            let (mut names, mut filenames) = (0, 0);
+            for param in params {
+                let key = param
+                    .splitn(2, |b| *b == b'=')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_ascii();
+                if key.eq_ignore_ascii_case(b"name") {
+                    names += 1;
+                } else if key.eq_ignore_ascii_case(b"filename") {
+                    filenames += 1;
+                }
+            }
+
+            if self.name && names == 0 {
+                return Err(Error::MissingFieldName);
+            }
+
+            if self.filename && filenames > 1 {
+                return Err(Error::DuplicateFilename);
+            }
+        } else if self.name {
+            return Err(Error::MissingFieldName);
+        }
+
+        if self.transfer_encoding {
+            for value in headers.get_all(CONTENT_TRANSFER_ENCODING) {
+                let encoding = String::from_utf8_lossy(value.as_bytes()).trim().to_string();
+                if !TRANSFER_ENCODINGS
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(&encoding))
+                {
+                    return Err(Error::InvalidTransferEncoding(encoding));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the `Content-Disposition` by `;`, but not in quoted strings.
+fn split_params(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut quoted = false;
+    let mut escaped = false;
+    bytes
+        .split(move |b| {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' if quoted => escaped = true,
+                b'"' => quoted = !quoted,
+                b';' if !quoted => return true,
+                _ => {}
+            }
+            false
+        })
+        .map(<[u8]>::trim_ascii)
+}
@@ -1,19 +1,35 @@
 use std::{
     fs::File,
     io::{Error as IoError, ErrorKind, Read, Write},
+    path::Path,
+    task::Poll,
 };
 
 use bytes::{Bytes, BytesMut};
-use http::{
-    header::{CONTENT_DISPOSITION, CONTENT_TYPE},
-    HeaderValue,
-};
+use http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use indexmap::IndexMap;
 use tracing::trace;
 
+#[cfg(feature = "sniff")]
+use crate::sniff;
+#[cfg(feature = "spool")]
+use crate::spool::create_spool_file;
+#[cfg(feature = "prealloc")]
+use crate::utils::{preallocate, shrink_preallocated};
+#[cfg(feature = "base64")]
+use crate::Base64Stream;
+#[cfg(feature = "decrypt")]
+use crate::Decrypt;
+#[cfg(feature = "json")]
+use crate::{graphql::GraphQl, JsonLines};
 use crate::{
-    utils::{parse_content_disposition, parse_content_type, parse_part_headers},
-    Error, Field, Flag, FormData, Result, State,
+    line_ending::Normalizer,
+    utils::{create_temp_file, decode_text, parse_content_type, persist_temp_file},
+    Error, Event, Events, Field, FieldMeta, FormData, LineEnding, Lines, ReadMode, Result, State,
+    Take,
 };
+#[cfg(feature = "spool")]
+use crate::{FieldData, Form};
 
 impl<T> Read for State<T>
 where
@@ -34,25 +50,12 @@ where
         loop {
             if self.is_readable {
                 // part
-                trace!("attempting to decode a part");
-
-                // field
-                if let Some(data) = self.decode() {
-                    trace!("part decoded from buffer");
-                    return Some(Ok(data));
+                if let Poll::Ready(data) = self.poll_buffered() {
+                    return data.map(Ok);
                 }
 
-                // field stream is ended
-                if Flag::Next == self.flag {
-                    return None;
-                }
-
-                // whole stream is ended
-                if Flag::Eof == self.flag {
-                    self.length -= self.buffer.len() as u64;
-                    self.buffer.clear();
-                    self.eof = true;
-                    return None;
+                if let Err(e) = self.check_multipart().and_then(|()| self.check_eof()) {
+                    return Some(Err(e));
                 }
 
                 self.is_readable = false;
@@ -65,19 +68,21 @@ where
                 continue;
             }
 
-            self.buffer.reserve(1);
+            // paces the reads
+            self.wait_rate_limit();
+
+            self.reserve_buffer(1);
             let mut b = BytesMut::new();
             b.resize(self.limits.buffer_size, 0);
             let bytect = match self.read(&mut b) {
                 Err(e) => return Some(Err(e.into())),
                 Ok(s) => {
                     let l = s as u64;
-                    if let Some(max) = self.limits.checked_stream_size(self.length + l) {
-                        return Some(Err(Error::PayloadTooLarge(max)));
+                    if let Err(e) = self.count_stream_size(l) {
+                        return Some(Err(e));
                     }
 
-                    self.buffer.extend_from_slice(&b.split_to(s));
-                    self.length += l;
+                    self.extend_buffer(&b.split_to(s));
                     l
                 }
             };
@@ -104,6 +109,35 @@ where
     }
 }
 
+impl<T> State<T>
+where
+    T: Read,
+{
+    /// Drains the rest data of the active field into buffer, see `ReadMode::Buffered`.
+    fn next_drain(&mut self, is_file: bool) -> Result<()> {
+        while let Some(buf) = self.next() {
+            self.drain(is_file, &buf?)?;
+        }
+
+        trace!(index = self.total - 1, "drained");
+        self.drained();
+
+        self.take_part_error()
+    }
+
+    /// Skips the rest data of the active field, the size limits are still applied.
+    fn next_skip(&mut self, is_file: bool) -> Result<()> {
+        while self.next().transpose()?.is_some() {
+            self.check_part_size(is_file, self.part_size())?;
+        }
+
+        trace!(index = self.total - 1, "skipped");
+        self.active = None;
+
+        self.take_part_error()
+    }
+}
+
 impl<T> Field<T>
 where
     T: Read,
@@ -117,10 +151,22 @@ where
         Ok(bytes.freeze())
     }
 
-    /// Copys bytes to a writer.
+    /// Reads field data to a UTF-8 string, see `Limits::strip_bom`.
+    pub fn text(&mut self) -> Result<String> {
+        let strip_bom = self.limits().is_ok_and(|limits| limits.strip_bom);
+        decode_text(Field::bytes(self)?, strip_bom)
+    }
+
+    /// Reads field data to bytes, then returns them with the metadata.
+    pub fn consume(mut self) -> Result<(FieldMeta, Bytes)> {
+        let bytes = Field::bytes(&mut self)?;
+        Ok((self.meta(), bytes))
+    }
+
+    /// Copys bytes to a writer, such as `Vec<u8>`, `&mut dyn Write` or a stdout lock.
     pub fn copy_to<W>(&mut self, writer: &mut W) -> Result<u64>
     where
-        W: Write + Send + Unpin + 'static,
+        W: Write + ?Sized,
     {
         let mut n = 0;
         while let Some(buf) = self.next() {
@@ -142,74 +188,363 @@ where
         Ok(n as u64)
     }
 
+    /// Copys text to a File, normalizes the line endings as it streams.
+    ///
+    /// Binary files should use the passthrough methods, like `copy_to_file`.
+    pub fn copy_to_file_normalized(&mut self, file: &mut File, mode: LineEnding) -> Result<u64> {
+        let mut normalizer = Normalizer::new(mode);
+        let mut out = Vec::new();
+        let mut n = 0;
+        while let Some(buf) = self.next() {
+            normalizer.normalize(&buf?, &mut out);
+            file.write_all(&out)?;
+            n += out.len();
+            out.clear();
+        }
+        normalizer.finish(&mut out);
+        file.write_all(&out)?;
+        n += out.len();
+        file.flush()?;
+        Ok(n as u64)
+    }
+
+    /// Copys bytes to a File, preallocates `hint` bytes before, such as the declared size.
+    ///
+    /// Reduces fragmentation and surfaces `ENOSPC` early,
+    /// the unwritten space is shrunk if field data is shorter than `hint`.
+    #[cfg(feature = "prealloc")]
+    pub fn copy_to_file_prealloc(&mut self, file: &mut File, hint: u64) -> Result<u64> {
+        let len = preallocate(file, hint)?;
+        let n = self.copy_to_file(file)?;
+        shrink_preallocated(file, len)?;
+        Ok(n)
+    }
+
+    /// Reads field data into memory, or spools it to a temp file if it is over
+    /// `Limits::spool_threshold`.
+    #[cfg(feature = "spool")]
+    pub fn spool(&mut self) -> Result<FieldData> {
+        let (threshold, dir) = self.spool_options()?;
+        let mut buffer = BytesMut::new();
+
+        while let Some(buf) = self.next().transpose()? {
+            if threshold.is_some_and(|max| buffer.len() + buf.len() > max) {
+                let mut file = create_spool_file(dir.as_deref())?;
+                file.write_all(&buffer)?;
+                file.write_all(&buf)?;
+                while let Some(buf) = self.next().transpose()? {
+                    file.write_all(&buf)?;
+                }
+                file.flush()?;
+                return Ok(FieldData::File(file));
+            }
+            buffer.extend_from_slice(&buf);
+        }
+
+        Ok(FieldData::Memory(buffer.freeze()))
+    }
+
+    /// Saves field data to a file atomically.
+    ///
+    /// Writes to a sibling temp file, then renames it to `path` once field data is fully read,
+    /// the temp file is removed on error, so no partial file is left at `path`.
+    pub fn save_atomic<P>(&mut self, path: P) -> Result<u64>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let (temp, mut file) = create_temp_file(path)?;
+        let res = self.copy_to_file(&mut file);
+        persist_temp_file(&temp, path, file, res)
+    }
+
     /// Ignores current field data, pass it.
+    ///
+    /// Field is still `fully_read` if no data was left to discard.
     pub fn ignore(&mut self) -> Result<()> {
-        while let Some(buf) = self.next() {
-            drop(buf?);
+        let mut discarded = false;
+        for buf in &mut *self {
+            discarded |= !buf?.is_empty();
+        }
+        if discarded {
+            self.fully_read = false;
         }
         Ok(())
     }
-}
 
-impl<T> Iterator for Field<T>
-where
-    T: Read,
-{
-    type Item = Result<Bytes>;
+    /// Skips the rest field data, returns `true` if any bytes were skipped,
+    /// `false` if the field was already fully consumed.
+    ///
+    /// The rest data is discarded by the state, see `Take::drain`,
+    /// it is not counted in `length`.
+    pub fn skip_to_end(&mut self) -> Result<bool> {
+        let mut skipped = self.peeked.take().is_some_and(|buf| !buf.is_empty());
+
+        if let Some(state) = self.state.clone() {
+            let mut state = state.try_lock().map_err(Error::from)?;
+
+            skipped |= match state.buffered.remove(&self.index) {
+                // the rest data is drained, see `ReadMode::Buffered`
+                Some(rest) => !rest.is_empty(),
+                None => match state.active {
+                    Some(is_file) => {
+                        state
+                            .next_skip(is_file)
+                            .map_err(|e| state.record_error(e))?;
+                        state.part_size() > self.length
+                    }
+                    None => false,
+                },
+            };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        trace!("polling {} {}", self.index, self.state.is_some());
+            trace!(length = self.length, "field is skipped");
+            drop(self.state.take());
+        }
 
-        let state = self.state.clone()?;
-        let mut state = state
-            .try_lock()
-            .map_err(|e| Error::TryLockError(e.to_string()))
-            .ok()?;
-        let is_file = self.filename.is_some();
+        if skipped {
+            self.fully_read = false;
+        }
+        Ok(skipped)
+    }
+
+    /// Maps each chunk of field data as it is read,
+    /// the limits still apply to the original data.
+    pub fn map_chunks<'a, F>(&'a mut self, mut f: F) -> impl Iterator<Item = Result<Bytes>> + 'a
+    where
+        F: FnMut(Bytes) -> Result<Bytes> + 'a,
+    {
+        self.map(move |res| res.and_then(&mut f))
+    }
+
+    /// Peeks at most `n` bytes of field data, they will be yielded again when reading.
+    pub fn peek(&mut self, n: usize) -> Result<Bytes> {
+        let mut peeked = self
+            .peeked
+            .take()
+            .map(|b| BytesMut::from(&b[..]))
+            .unwrap_or_default();
+
+        while peeked.len() < n {
+            match self.next_state() {
+                Some(buf) => peeked.extend_from_slice(&buf?),
+                None => break,
+            }
+        }
+
+        let peeked = peeked.freeze();
+        let bytes = peeked.slice(..n.min(peeked.len()));
 
-        match state.next().and_then(Result::ok) {
+        if !peeked.is_empty() {
+            self.peeked.replace(peeked);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Verifies the declared `content_type` by sniffing the magic number of field data.
+    ///
+    /// Field data is peeked, so it is still fully readable after verification.
+    #[cfg(feature = "sniff")]
+    pub fn verify_content_type(&mut self) -> Result<bool> {
+        let bytes = self.peek(sniff::MAX_MAGIC_LEN)?;
+        Ok(sniff::verify(self.content_type.as_ref(), &bytes))
+    }
+
+    fn next_state(&mut self) -> Option<Result<Bytes>> {
+        let span = self.span.clone();
+        let _enter = span.enter();
+        trace!(consumed = self.state.is_none(), "polling field");
+
+        let state = self.state.clone()?;
+        let mut state = match state.try_lock() {
+            Ok(state) => state,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        let next = match state.buffered.get_mut(&self.index) {
+            // the rest data is drained, see `ReadMode::Buffered`
+            Some(rest) => (!rest.is_empty()).then(|| rest.split().freeze()),
+            None => match state.next().transpose() {
+                Ok(next) => next,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+
+        match next {
             None => {
-                trace!("polled {}", self.index);
+                if state.buffered.remove(&self.index).is_none() {
+                    state.active = None;
+                }
+                trace!(length = self.length, "field is ended");
+
+                // the part is ended, the field can't be read any further
+                if let Err(e) = self.check_ended(&mut state) {
+                    let e = state.record_error(e);
+                    drop(self.state.take());
+                    return Some(Err(e));
+                }
+
                 drop(self.state.take());
+                self.fully_read = true;
+
+                if let Some(hook) = state.on_field_complete.as_mut() {
+                    hook(&self.meta(), self.length as u64);
+                }
+
                 None
             }
             Some(buf) => {
                 let l = buf.len();
 
-                if is_file {
-                    if let Some(max) = state.limits.checked_file_size(self.length + l) {
-                        return Some(Err(Error::FileTooLarge(max)));
-                    }
-                } else if let Some(max) = state.limits.checked_field_size(self.length + l) {
-                    return Some(Err(Error::FieldTooLarge(max)));
+                if let Err(e) = self.check_size(&state.limits, l) {
+                    return Some(Err(e));
                 }
 
-                self.length += l;
-                trace!("polled bytes {}/{}", buf.len(), self.length);
+                #[cfg(feature = "json")]
+                if let Some(graphql) = state.graphql.as_mut().filter(|_| self.is_graphql_map()) {
+                    graphql.extend_map(&buf);
+                }
+
+                #[cfg(feature = "checksum")]
+                if let Some(hasher) = self.hasher.as_mut() {
+                    hasher.update(&buf);
+                }
+
+                self.length = self.length.saturating_add(l);
+                trace!(bytes = l, length = self.length, "polled bytes");
                 Some(Ok(buf))
             }
         }
     }
 }
 
-/// Reads form-data from request payload body, then yields `Field`
-impl<T> Iterator for FormData<T>
+impl<T> Iterator for Field<T>
 where
     T: Read,
 {
-    type Item = Result<Field<T>>;
+    type Item = Result<Bytes>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut state = self
-            .state
-            .try_lock()
-            .map_err(|e| Error::TryLockError(e.to_string()))
-            .ok()?;
+        // yields the peeked bytes first
+        if let Some(buf) = self.peeked.take() {
+            return Some(Ok(buf));
+        }
+
+        match self.next_state() {
+            Some(Err(e)) => Some(Err(self.map_error(e))),
+            res => res,
+        }
+    }
+}
+
+impl<T> FormData<T>
+where
+    T: Read,
+{
+    /// Reads all fields, the large field data is spooled to temp files,
+    /// see `Field::spool`.
+    #[cfg(feature = "spool")]
+    pub fn buffered(&mut self) -> Result<Vec<(FieldMeta, FieldData)>> {
+        let mut fields = Vec::new();
+        while let Some(mut field) = self.next().transpose()? {
+            let data = field.spool()?;
+            fields.push((field.meta(), data));
+        }
+        Ok(fields)
+    }
+
+    /// Reads all text fields into a map, the values with the same name are grouped in order.
+    ///
+    /// Fails with `Error::UnexpectedFile` on file parts, unless `Limits::allow_files_ignored`.
+    pub fn collect_text_map(&mut self) -> Result<IndexMap<String, Vec<String>>> {
+        let limits = self.limits()?;
+        let mut map = IndexMap::<String, Vec<String>>::new();
+
+        while let Some(mut field) = self.next().transpose()? {
+            if field.filename.is_some() {
+                if !limits.allow_files_ignored {
+                    return Err(self.map_error(Error::UnexpectedFile(field.name)));
+                }
+                field.ignore()?;
+                continue;
+            }
+
+            let count = map.get(&field.name).map_or(0, Vec::len) + 1;
+            if let Some(max) = limits.checked_values_per_name(count) {
+                return Err(self.map_error(Error::ValuesTooMany {
+                    name: field.name,
+                    max,
+                }));
+            }
+
+            let value = field.text()?;
+            map.entry(field.name).or_default().push(value);
+        }
+
+        Ok(map)
+    }
+
+    /// Reads the entire form, the large files are spooled to temp files,
+    /// see `Field::spool`.
+    #[cfg(feature = "spool")]
+    pub fn collect(mut self) -> Result<Form> {
+        let mut form = Form::default();
+        while let Some(mut field) = self.next().transpose()? {
+            if field.filename.is_some() {
+                let data = field.spool()?;
+                form.push_file(field.meta(), data);
+            } else {
+                let value = field.text()?;
+                form.push_text(field.name, value);
+            }
+        }
+        Ok(form)
+    }
 
-        match state.next()? {
-            Err(e) => Some(Err(e)),
-            Ok(buf) => {
-                trace!("parse part");
+    fn next_field(&self) -> Option<Result<Field<T>>> {
+        let mut state = match self.state.try_lock() {
+            Ok(state) => state,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        if let Some(is_file) = state.active {
+            match state.read_mode {
+                ReadMode::Sequential | ReadMode::Strict => {
+                    return Some(Err(Error::FieldStillActive));
+                }
+                ReadMode::Buffered => {
+                    if let Err(e) = state.next_drain(is_file) {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+
+        // the previous part is suspicious
+        if let Err(e) = state.take_part_error() {
+            return Some(Err(e));
+        }
+
+        match state.next() {
+            None => {
+                trace!(total = state.total, length = state.length, "parse eof");
+
+                // too few parts
+                if let Some(min) = state.limits.checked_min_parts(state.total) {
+                    return Some(Err(Error::TooFewParts { min }));
+                }
+
+                // files referenced by graphql map are missing
+                #[cfg(feature = "json")]
+                if let Some(Err(e)) = state.graphql.as_ref().map(GraphQl::check) {
+                    return Some(Err(e));
+                }
+
+                None
+            }
+            Some(Err(e)) => Some(Err(e)),
+            Some(Ok(buf)) => {
+                trace!(header_size = buf.len(), "parse part");
 
                 // too many parts
                 if let Some(max) = state.limits.checked_parts(state.total + 1) {
@@ -217,24 +552,20 @@ where
                 }
 
                 // invalid part header
-                let Ok(mut headers) = parse_part_headers(&buf) else {
-                    return Some(Err(Error::InvalidHeader));
+                let mut headers = match state.parse_part_headers(&buf) {
+                    Ok(headers) => headers,
+                    Err(e) => return Some(Err(e)),
                 };
 
                 // invalid content disposition
-                let Some((name, filename)) = headers
-                    .remove(CONTENT_DISPOSITION)
-                    .as_ref()
-                    .map(HeaderValue::as_bytes)
-                    .map(parse_content_disposition)
-                    .and_then(Result::ok)
-                else {
-                    return Some(Err(Error::InvalidContentDisposition));
+                let disposition = headers.remove(CONTENT_DISPOSITION);
+                let (name, filename) = match state.parse_content_disposition(disposition.as_ref()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return Some(Err(e)),
                 };
 
-                // field name is too long
-                if let Some(max) = state.limits.checked_field_name_size(name.len()) {
-                    return Some(Err(Error::FieldNameTooLong(max)));
+                if let Err(e) = state.check_field_name(&name) {
+                    return Some(Err(e));
                 }
 
                 if filename.is_some() {
@@ -242,6 +573,17 @@ where
                     if let Some(max) = state.limits.checked_files(state.files + 1) {
                         return Some(Err(Error::FilesTooMany(max)));
                     }
+                    // content type is not allowed
+                    let content_type = parse_content_type(headers.get(CONTENT_TYPE))
+                        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+                    if state
+                        .limits
+                        .checked_content_type(&name, &content_type)
+                        .is_some()
+                    {
+                        return Some(Err(Error::ContentTypeNotAllowed { name, content_type }));
+                    }
+
                     state.files += 1;
                 } else {
                     // fields too many
@@ -255,17 +597,247 @@ where
                 let mut field = Field::empty();
 
                 field.name = name;
+                field.raw_content_disposition = disposition;
                 field.filename = filename;
                 field.index = state.index();
-                field.content_type = parse_content_type(headers.remove(CONTENT_TYPE).as_ref());
+                field.body_start = Some(state.part_started(&headers, buf.len()));
+                state.active = Some(field.filename.is_some());
+                field.header_size = buf.len();
+                field.content_type = parse_content_type(headers.get(CONTENT_TYPE));
+                if !state.byteranges {
+                    headers.remove(CONTENT_TYPE);
+                }
+
+                #[cfg(feature = "json")]
+                if let Some(graphql) = state.graphql.as_mut() {
+                    if field.filename.is_some() {
+                        if let Err(e) = graphql.file(&field.name) {
+                            return Some(Err(e));
+                        }
+                    } else if field.content_type.is_none() {
+                        field.content_type = GraphQl::content_type(&field.name);
+                    }
+                }
+
                 field.state_mut().replace(self.state());
 
                 if !headers.is_empty() {
                     field.headers_mut().replace(headers);
                 }
 
+                field.init_span();
+
                 Some(Ok(field))
             }
         }
     }
 }
+
+/// Reads form-data from request payload body, then yields `Field`
+impl<T> Iterator for FormData<T>
+where
+    T: Read,
+{
+    type Item = Result<Field<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_field() {
+            Some(Err(e)) => Some(Err(self.map_error(e))),
+            res => res,
+        }
+    }
+}
+
+/// Reads form-data from request payload body, then yields `Event`
+impl<T> Iterator for Events<T>
+where
+    T: Read,
+{
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(field) = self.field.as_mut() {
+            return match field.next() {
+                Some(Err(e)) => Some(Err(e)),
+                Some(Ok(buf)) => Some(Ok(Event::Data(buf))),
+                None => {
+                    let length = self.field.take().map_or(0, |field| field.length);
+                    Some(Ok(Event::PartEnd { length }))
+                }
+            };
+        }
+
+        if self.completed {
+            return None;
+        }
+
+        match self.form.next() {
+            Some(Err(e)) => Some(Err(e)),
+            Some(Ok(field)) => {
+                let meta = field.meta();
+                self.field.replace(field);
+                Some(Ok(Event::PartStart(Box::new(meta))))
+            }
+            None => {
+                self.completed = true;
+                Some(Ok(Event::Complete))
+            }
+        }
+    }
+}
+
+impl<T> Take<T>
+where
+    T: Read,
+{
+    /// Reads the rest of body, the data of unread fields is discarded.
+    ///
+    /// The limits are still applied, such as `Limits::file_size` and `Limits::stream_size`.
+    pub fn drain(mut self) -> Result<()> {
+        {
+            let mut state = self.form.state.try_lock().map_err(Error::from)?;
+            if let Some(is_file) = state.active {
+                state
+                    .next_skip(is_file)
+                    .map_err(|e| state.record_error(e))?;
+            }
+        }
+
+        while let Some(mut field) = self.form.next().transpose()? {
+            field.ignore()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads form-data from request payload body, then yields at most `n` `Field`
+impl<T> Iterator for Take<T>
+where
+    T: Read,
+{
+    type Item = Result<Field<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let res = self.form.next();
+        if let Some(Ok(_)) = res {
+            self.remaining -= 1;
+        }
+        res
+    }
+}
+
+/// Reads field data, then yields lines
+impl<T> Iterator for Lines<'_, T>
+where
+    T: Read,
+{
+    type Item = Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.split_line() {
+                return Some(Ok(line));
+            }
+
+            if self.eof {
+                return None;
+            }
+
+            match self.field.next() {
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(buf)) => self.buffer.extend_from_slice(&buf),
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields base64 encoded chunks
+#[cfg(feature = "base64")]
+impl<T> Iterator for Base64Stream<'_, T>
+where
+    T: Read,
+{
+    type Item = Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.eof {
+                return self.finish().map(Ok);
+            }
+
+            match self.field.next() {
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(buf)) => {
+                    if let Some(chunk) = self.encode(&buf) {
+                        return Some(Ok(chunk));
+                    }
+                }
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields decrypted chunks
+#[cfg(feature = "decrypt")]
+impl<T> Iterator for Decrypt<'_, T>
+where
+    T: Read,
+{
+    type Item = Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.eof {
+                return self.finish();
+            }
+
+            match self.field.next() {
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(buf)) => {
+                    if let Some(chunk) = self.update(&buf) {
+                        return Some(Ok(chunk));
+                    }
+                }
+                None => self.eof = true,
+            }
+        }
+    }
+}
+
+/// Reads field data, then yields JSON values
+#[cfg(feature = "json")]
+impl<T, D> Iterator for JsonLines<'_, T, D>
+where
+    T: Read,
+    D: serde::de::DeserializeOwned,
+{
+    type Item = Result<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.failed {
+                return None;
+            }
+
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let res = serde_json::from_slice(&line).map_err(Error::Json);
+            self.failed = res.is_err() && self.fail_fast;
+            return Some(res);
+        }
+    }
+}
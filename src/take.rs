@@ -0,0 +1,41 @@
+use std::fmt;
+
+use crate::FormData;
+
+/// Take, yields at most `n` fields of `FormData`, see `FormData::take`.
+///
+/// Dropping it leaves the rest of body unread, an HTTP/1.1 server has to close
+/// the connection instead of reusing it for keep-alive. Calls `drain` to read
+/// the rest of body, then the connection can be reused.
+pub struct Take<T> {
+    pub(crate) form: FormData<T>,
+    pub(crate) remaining: usize,
+}
+
+impl<T> Take<T> {
+    /// Creates new `Take`.
+    #[must_use]
+    pub fn new(form: FormData<T>, n: usize) -> Self {
+        Self { form, remaining: n }
+    }
+
+    /// Gets the `FormData`.
+    #[must_use]
+    pub fn form(&self) -> &FormData<T> {
+        &self.form
+    }
+
+    /// Gets the number of fields can still be yielded.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<T> fmt::Debug for Take<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Take")
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
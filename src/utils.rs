@@ -1,3 +1,11 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
 use http::header::{HeaderMap, HeaderName, HeaderValue};
 use httparse::{parse_headers, Status, EMPTY_HEADER};
 
@@ -7,12 +15,138 @@ pub(crate) const MAX_HEADERS: usize = 8 * 2;
 pub(crate) const DASHES: [u8; 2] = [b'-', b'-']; // `--`
 pub(crate) const CRLF: [u8; 2] = [b'\r', b'\n']; // `\r\n`
 pub(crate) const CRLFS: [u8; 4] = [b'\r', b'\n', b'\r', b'\n']; // `\r\n\r\n`
+pub(crate) const BOM: [u8; 3] = [0xEF, 0xBB, 0xBF]; // UTF-8 BOM
 
 const NAME: &[u8; 4] = b"name";
 const FILE_NAME: &[u8; 8] = b"filename";
 const FORM_DATA: &[u8; 9] = b"form-data";
 const SHORTEST_CONTENT_DISPOSITION: &[u8; 19] = b"form-data; name=\"s\"";
 
+/// Creates a sibling temp file of `path`, like `.name.pid-nanos.tmp`.
+pub(crate) fn create_temp_file(path: &Path) -> Result<(PathBuf, File)> {
+    let name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+
+    let mut temp = path.to_path_buf();
+    temp.set_file_name(format!(
+        ".{}.{}-{}.tmp",
+        name.to_string_lossy(),
+        process::id(),
+        nanos
+    ));
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)?;
+
+    Ok((temp, file))
+}
+
+/// Renames the temp file to `path` if data is fully written, otherwise removes it.
+pub(crate) fn persist_temp_file(
+    temp: &Path,
+    path: &Path,
+    file: File,
+    res: Result<u64>,
+) -> Result<u64> {
+    let res = res.and_then(|n| {
+        file.sync_all()?;
+        drop(file);
+        fs::rename(temp, path)?;
+        Ok(n)
+    });
+
+    if res.is_err() {
+        let _ = fs::remove_file(temp);
+    }
+
+    res
+}
+
+/// Preallocates `hint` bytes from current position of file, returns the length of file before.
+///
+/// Unsupported preallocation is ignored, but `ENOSPC` is surfaced early.
+#[cfg(feature = "prealloc")]
+pub(crate) fn preallocate(file: &mut File, hint: u64) -> Result<u64> {
+    use std::io::{ErrorKind, Seek};
+
+    let len = file.metadata()?.len();
+    let end = file.stream_position()? + hint;
+
+    if end > len {
+        if let Err(e) = fs2::FileExt::allocate(&*file, end) {
+            if e.kind() == ErrorKind::StorageFull {
+                return Err(e.into());
+            }
+            tracing::trace!(error = %e, "preallocation is unsupported");
+        }
+    }
+
+    Ok(len)
+}
+
+/// Shrinks the preallocated but unwritten space of file.
+#[cfg(feature = "prealloc")]
+pub(crate) fn shrink_preallocated(file: &mut File, len: u64) -> Result<()> {
+    use std::io::Seek;
+
+    let len = len.max(file.stream_position()?);
+    if file.metadata()?.len() > len {
+        file.set_len(len)?;
+    }
+
+    Ok(())
+}
+
+/// Creates a new file in `dir`, named `name`, or `name-1.ext`, `name-2.ext` if it exists.
+///
+/// The existing files are never overwritten.
+#[cfg(all(feature = "tokio", feature = "async", not(feature = "sync")))]
+pub(crate) async fn create_unique_file(
+    dir: &Path,
+    name: &std::ffi::OsStr,
+) -> Result<(PathBuf, tokio::fs::File)> {
+    let original = Path::new(name);
+    let mut path = dir.join(name);
+    let mut n = 0;
+
+    loop {
+        match tokio::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        n += 1;
+        let mut unique = original.file_stem().unwrap_or(name).to_os_string();
+        unique.push(format!("-{n}"));
+        if let Some(ext) = original.extension() {
+            unique.push(".");
+            unique.push(ext);
+        }
+        path = dir.join(unique);
+    }
+}
+
+/// Decodes text field data, strips the leading UTF-8 BOM if `strip_bom`.
+pub(crate) fn decode_text(bytes: Bytes, strip_bom: bool) -> Result<String> {
+    let bytes = match bytes.strip_prefix(&BOM) {
+        Some(rest) if strip_bom => rest.to_vec(),
+        _ => bytes.into(),
+    };
+    Ok(String::from_utf8(bytes)?)
+}
+
 pub(crate) fn parse_content_type(header: Option<&HeaderValue>) -> Option<mime::Mime> {
     header
         .map(HeaderValue::to_str)
@@ -0,0 +1,62 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::FormData;
+
+const BOUNDARY: &str = "AaB03x";
+
+fn body(data: &[u8], sizes: &[usize]) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"file.bin\"\r\n\r\n").as_bytes());
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let mut chunks = Vec::new();
+    let mut rest = &payload[..];
+    for size in sizes.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at(rest.len().min(*size));
+        chunks.push(Ok(Bytes::copy_from_slice(chunk)));
+        rest = tail;
+    }
+    chunks
+}
+
+#[tokio::test]
+async fn base64_stream() -> Result<()> {
+    let data = (0..1000u32)
+        .map(|n| (n * 7).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+
+    for len in [0, 1, 2, 3, 4, 5, 1000] {
+        for sizes in [&[1][..], &[2, 5], &[3, 7, 11], &[4096]] {
+            let data = &data[..len];
+            let mut form = FormData::new(stream::iter(body(data, sizes)), BOUNDARY);
+
+            let mut field = form.try_next().await?.expect("file");
+            let chunks = field.base64_stream().try_collect::<Vec<_>>().await?;
+
+            if let Some((last, chunks)) = chunks.split_last() {
+                // padding only at the end
+                for chunk in chunks {
+                    assert_eq!(chunk.len() % 4, 0);
+                    assert!(!chunk.contains(&b'='));
+                }
+                assert_eq!(last.len() % 4, 0);
+            }
+
+            let encoded = chunks.concat();
+            assert_eq!(encoded, STANDARD.encode(data).as_bytes());
+            assert_eq!(STANDARD.decode(&encoded)?, data);
+            assert_eq!(field.length, len);
+
+            assert!(form.try_next().await?.is_none());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use bytes::Bytes;
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::{Checksum, FormData};
+
+const BOUNDARY: &str = "AaB03x";
+
+fn body(data: &[u8], sizes: &[usize]) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"file.bin\"\r\n\r\n").as_bytes());
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let mut chunks = Vec::new();
+    let mut rest = &payload[..];
+    for size in sizes.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at(rest.len().min(*size));
+        chunks.push(Ok(Bytes::copy_from_slice(chunk)));
+        rest = tail;
+    }
+    chunks
+}
+
+async fn checksum(data: &[u8], sizes: &[usize], checksum: Checksum) -> Result<Vec<u8>> {
+    let mut form = FormData::new(stream::iter(body(data, sizes)), BOUNDARY);
+    let mut field = form
+        .try_next()
+        .await?
+        .expect("file")
+        .with_checksum(checksum);
+
+    // peeked data is not counted twice
+    assert_eq!(field.peek(4).await?, data[..4.min(data.len())]);
+    assert_eq!(field.bytes().await?, data);
+
+    Ok(field.finalize_checksum())
+}
+
+#[tokio::test]
+async fn crc32() -> Result<()> {
+    for sizes in [&[1][..], &[2, 5], &[3, 7, 11], &[4096]] {
+        assert_eq!(
+            checksum(b"123456789", sizes, Checksum::Crc32).await?,
+            0xCBF4_3926_u32.to_be_bytes()
+        );
+        assert_eq!(
+            checksum(b"", sizes, Checksum::Crc32).await?,
+            0_u32.to_be_bytes()
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn adler32() -> Result<()> {
+    for sizes in [&[1][..], &[2, 5], &[4096]] {
+        assert_eq!(
+            checksum(b"Wikipedia", sizes, Checksum::Adler32).await?,
+            0x11E6_0398_u32.to_be_bytes()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "sha2")]
+#[tokio::test]
+async fn sha256() -> Result<()> {
+    for sizes in [&[1][..], &[2, 5], &[4096]] {
+        assert_eq!(
+            checksum(b"abc", sizes, Checksum::Sha256).await?,
+            [
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad,
+            ]
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn without_checksum() -> Result<()> {
+    let mut form = FormData::new(stream::iter(body(b"data", &[4096])), BOUNDARY);
+    let mut field = form.try_next().await?.expect("file");
+    assert_eq!(field.bytes().await?, "data");
+    assert!(field.finalize_checksum().is_empty());
+
+    Ok(())
+}
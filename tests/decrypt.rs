@@ -0,0 +1,104 @@
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+use anyhow::Result;
+use bytes::Bytes;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::{Cipher, Error, FormData};
+
+const BOUNDARY: &str = "AaB03x";
+const KEY: [u8; 32] = [7; 32];
+
+fn body(data: &[u8], size: usize) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"file.bin\"\r\n\r\n").as_bytes());
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    payload
+        .chunks(size)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect()
+}
+
+#[tokio::test]
+async fn decrypt_ctr() -> Result<()> {
+    let plaintext = (0..1000u32)
+        .map(|n| (n * 7).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+    let nonce = [1; 16];
+
+    let mut data = plaintext.clone();
+    ctr::Ctr128BE::<aes::Aes256>::new(&KEY.into(), &nonce.into()).apply_keystream(&mut data);
+
+    for size in [1, 7, 15, 16, 17, 4096] {
+        let mut form = FormData::new(stream::iter(body(&data, size)), BOUNDARY);
+        form.set_max_buf_size(64)?;
+
+        let mut field = form.try_next().await?.expect("file");
+        let chunks = field
+            .decrypt(&KEY, &nonce, Cipher::Aes256Ctr)?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // decrypted incrementally
+        if size < plaintext.len() {
+            assert!(chunks.len() > 1);
+        }
+        assert_eq!(chunks.concat(), plaintext);
+        assert_eq!(field.length, plaintext.len());
+
+        assert!(form.try_next().await?.is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn decrypt_gcm() -> Result<()> {
+    let plaintext = b"Alpha file content.\r\n".repeat(100);
+    let nonce = [2; 12];
+
+    let cipher = Aes256Gcm::new(&KEY.into());
+    let data = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    for size in [1, 13, 4096] {
+        let mut form = FormData::new(stream::iter(body(&data, size)), BOUNDARY);
+
+        let mut field = form.try_next().await?.expect("file");
+        let chunks = field
+            .decrypt(&KEY, &nonce, Cipher::Aes256Gcm)?
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks.concat(), plaintext);
+
+        assert!(form.try_next().await?.is_none());
+    }
+
+    // tampered
+    let mut tampered = data.clone();
+    tampered[0] ^= 1;
+    let mut form = FormData::new(stream::iter(body(&tampered, 64)), BOUNDARY);
+    let mut field = form.try_next().await?.expect("file");
+    assert!(matches!(
+        field
+            .decrypt(&KEY, &nonce, Cipher::Aes256Gcm)?
+            .try_collect::<Vec<_>>()
+            .await,
+        Err(Error::Decrypt(_))
+    ));
+
+    // invalid nonce
+    let mut form = FormData::new(stream::iter(body(&data, 64)), BOUNDARY);
+    let mut field = form.try_next().await?.expect("file");
+    assert!(matches!(
+        field.decrypt(&KEY, &[0; 16], Cipher::Aes256Gcm),
+        Err(Error::Decrypt(_))
+    ));
+
+    Ok(())
+}
@@ -1,12 +1,29 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
 use anyhow::Result;
 use async_fs::File;
 
-use bytes::BytesMut;
+use bytes::{Buf, Bytes, BytesMut};
 use http::HeaderMap;
+use tempfile::tempdir;
 
-use futures_util::stream::TryStreamExt;
+use futures_util::{
+    future::{self, FutureExt},
+    io,
+    stream::{self, StreamExt, TryStreamExt},
+    task::noop_waker_ref,
+};
 
-use form_data::{Error, FormData};
+use form_data::{
+    BoundarySearcher, BufStream, Builder, Error, ErrorContext, Event, Field, FormData, Limits,
+    LineEnding, ReadMode, StateSnapshot,
+};
 
 #[path = "./lib/mod.rs"]
 mod lib;
@@ -83,6 +100,8 @@ async fn filename_with_space() -> Result<()> {
             match field.index {
                 0 => {
                     assert_eq!(field.name, "person");
+                    assert_eq!(field.name(), "person");
+                    assert_eq!(field.filename(), None);
                     assert_eq!(field.content_type, None);
                     assert_eq!(field.length, 9);
                     assert_eq!(buffer, "anonymous");
@@ -90,6 +109,7 @@ async fn filename_with_space() -> Result<()> {
                 1 => {
                     assert_eq!(field.name, "secret");
                     assert_eq!(field.filename, Some("foo bar.txt".to_string()));
+                    assert_eq!(field.filename(), Some("foo bar.txt"));
                     assert_eq!(field.content_type, Some(mime::TEXT_PLAIN));
                     assert_eq!(field.length, 20);
                     assert_eq!(buffer, "contents of the file");
@@ -308,6 +328,10 @@ async fn headers() -> Result<()> {
             assert_eq!(field.name, "operations");
             assert_eq!(field.filename, Some("graphql.json".into()));
             assert_eq!(field.content_type, Some(mime::APPLICATION_JSON));
+            assert_eq!(
+                field.raw_content_disposition(),
+                Some(&b"form-data; name=\"operations\"; filename=\"graphql.json\""[..])
+            );
             assert_eq!(field.length, 13);
             let mut headers = HeaderMap::new();
             headers.append(http::header::CONTENT_LENGTH, 13.into());
@@ -421,6 +445,41 @@ async fn sample() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn body_range() -> Result<()> {
+    let payload = std::fs::read("tests/fixtures/sample.txt")?;
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
+    let limit = body.limit();
+
+    let mut form = FormData::new(body, "--------------------------434049563556637648550474");
+    form.set_max_buf_size(limit)?;
+
+    let mut ranges = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        assert_eq!(field.body_range(), None);
+
+        let buffer = field.bytes().await?;
+        let (start, end) = field.body_range().expect("range");
+
+        assert_eq!(end - start, buffer.len() as u64);
+        assert_eq!(
+            payload[usize::try_from(start)?..usize::try_from(end)?],
+            buffer
+        );
+        ranges.push((start, end));
+    }
+
+    assert_eq!(ranges.len(), 5);
+    // the parts are in order, separated by the headers and delimiters
+    for pair in ranges.windows(2) {
+        assert!(pair[0].1 < pair[1].0);
+    }
+    assert!(ranges[4].1 < payload.len() as u64);
+    assert_eq!(Field::<()>::empty().body_range(), None);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn sample_lf() -> Result<()> {
     let body = Limited::random(File::open("tests/fixtures/sample.lf.txt").await?);
@@ -531,9 +590,8 @@ async fn graphql_random() -> Result<()> {
 }
 
 #[tokio::test]
-async fn graphql_1024() -> Result<()> {
-    let body = Limited::random_with(File::open("tests/fixtures/graphql.txt").await?, 1024);
-    // let body = Limited::new(File::open("tests/fixtures/graphql.txt").await?, 1033);
+async fn graphql_1033() -> Result<()> {
+    let body = Limited::new(File::open("tests/fixtures/graphql.txt").await?, 1033);
     let limit = body.limit();
 
     let mut form = FormData::new(body, "------------------------627436eaefdbc285");
@@ -606,75 +664,2156 @@ async fn graphql_1024() -> Result<()> {
 }
 
 #[tokio::test]
-async fn graphql_1033() -> Result<()> {
-    let body = Limited::new(File::open("tests/fixtures/graphql.txt").await?, 1033);
+async fn consume() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
     let limit = body.limit();
 
-    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+    let mut form = FormData::new(body, "--------------------------434049563556637648550474");
     form.set_max_buf_size(limit)?;
 
-    while let Some(mut field) = form.try_next().await? {
-        assert!(!field.consumed());
-        assert_eq!(field.length, 0);
+    let mut metas = Vec::new();
 
-        let mut buffer = BytesMut::new();
-        while let Some(buf) = field.try_next().await? {
-            buffer.extend_from_slice(&buf);
+    while let Some(field) = form.try_next().await? {
+        let (meta, buffer) = tokio::spawn(field.consume()).await??;
+
+        assert_eq!(meta.length, buffer.len());
+
+        if meta.index == 2 {
+            assert_eq!(meta.name, "file");
+            assert_eq!(meta.filename, Some("tsconfig.json".into()));
+            assert_eq!(meta.content_type, Some(mime::APPLICATION_OCTET_STREAM));
+            assert_eq!(meta.length, 233);
         }
 
+        metas.push(meta);
+    }
+
+    assert_eq!(
+        metas.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(),
+        ["foo", "bar", "file", "file2", "crab"]
+    );
+    assert_eq!(
+        metas.iter().map(|m| m.length).collect::<Vec<_>>(),
+        [3, 3, 233, 28, 0]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn min_parts() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/zero-parts.txt").await?);
+    let mut form = FormData::new(body, "AaB03x");
+
+    assert!(form.try_next().await?.is_none());
+
+    let body = Limited::random(File::open("tests/fixtures/zero-parts.txt").await?);
+    let mut form = FormData::with_limits(body, "AaB03x", Limits::default().min_parts(1));
+
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::TooFewParts { min: 1 })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn peek() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
+    let limit = body.limit();
+
+    let mut form = FormData::new(body, "--------------------------434049563556637648550474");
+    form.set_max_buf_size(limit)?;
+
+    while let Some(mut field) = form.try_next().await? {
+        let peeked = field.peek(16).await?;
+        assert_eq!(peeked, field.peek(16).await?);
+
+        let buffer = field.bytes().await?;
+        assert_eq!(field.length, buffer.len());
+        assert!(buffer.starts_with(&peeked));
+
         match field.index {
-            0 => {
-                assert_eq!(field.name, "operations");
-                assert_eq!(field.filename, None);
-                assert_eq!(field.content_type, None);
-                assert_eq!(field.length, 236);
-                assert_eq!(buffer, "[{ \"query\": \"mutation ($file: Upload!) { singleUpload(file: $file) { id } }\", \"variables\": { \"file\": null } }, { \"query\": \"mutation($files: [Upload!]!) { multipleUpload(files: $files) { id } }\", \"variables\": { \"files\": [null, null] } }]");
-            }
-            1 => {
-                assert_eq!(field.name, "map");
-                assert_eq!(field.filename, None);
-                assert_eq!(field.content_type, None);
-                assert_eq!(field.length, 89);
-                assert_eq!(buffer, "{ \"0\": [\"0.variables.file\"], \"1\": [\"1.variables.files.0\"], \"2\": [\"1.variables.files.1\"] }");
-            }
-            2 => {
-                assert_eq!(field.name, "0");
-                assert_eq!(field.filename, Some("a.txt".into()));
-                assert_eq!(field.content_type, Some(mime::TEXT_PLAIN));
-                assert_eq!(field.length, 21);
-                assert_eq!(buffer, "Alpha file content.\r\n");
-            }
-            3 => {
-                assert_eq!(field.name, "1");
-                assert_eq!(field.filename, Some("b.txt".into()));
-                assert_eq!(field.content_type, Some(mime::TEXT_PLAIN));
-                assert_eq!(field.length, 21);
-                assert_eq!(buffer, "Bravo file content.\r\n");
-            }
-            4 => {
-                assert_eq!(field.name, "2");
-                assert_eq!(field.filename, Some("c.txt".into()));
-                assert_eq!(field.content_type, Some(mime::TEXT_PLAIN));
-                assert_eq!(field.length, 23);
-                assert_eq!(buffer, "Charlie file content.\r\n");
-            }
+            0 => assert_eq!(peeked, "foo"),
+            2 => assert_eq!(peeked, "{\r\n  \"compilerOp"),
+            4 => assert_eq!(peeked, ""),
             _ => {}
         }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn buf_stream() -> Result<()> {
+    let chunk = Bytes::from_static(b"zero-copy");
+    let ptr = chunk.as_ptr();
+
+    let mut body = BufStream::new(stream::iter(vec![Ok::<_, io::Error>(chunk)]));
+    assert_eq!(body.try_next().await?.map(|b| b.as_ptr()), Some(ptr));
+
+    let chained = Bytes::from_static(b"non-").chain(Bytes::from_static(b"contiguous"));
+    let mut body = BufStream::new(stream::iter(vec![Ok::<_, io::Error>(chained)]));
+    assert_eq!(
+        body.try_next().await?,
+        Some(Bytes::from_static(b"non-contiguous"))
+    );
+
+    let payload = async_fs::read("tests/fixtures/graphql.txt").await?;
+    let chunks = payload
+        .chunks(64)
+        .map(|c| {
+            let (a, b) = c.split_at(c.len() / 2);
+            Ok::<_, io::Error>(Bytes::copy_from_slice(a).chain(Bytes::copy_from_slice(b)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut form = FormData::new(
+        BufStream::new(stream::iter(chunks)),
+        "------------------------627436eaefdbc285",
+    );
+
+    let mut lengths = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        lengths.push(field.bytes().await?.len());
+    }
+    assert_eq!(lengths, [236, 89, 21, 21, 23]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_limits() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
+    let limit = body.limit();
+
+    let mut form = FormData::with_limits(
+        body,
+        "--------------------------434049563556637648550474",
+        Limits::default().file_size(16),
+    );
+    form.set_max_buf_size(limit)?;
+
+    assert_eq!(form.limits()?.file_size, Some(16));
+    assert_eq!(form.limits()?.buffer_size, limit);
 
+    form.set_limits(form.limits()?.file_size(1024))?;
+
+    assert_eq!(form.limits()?.file_size, Some(1024));
+    assert_eq!(form.limits()?.buffer_size, limit);
+
+    while let Some(mut field) = form.try_next().await? {
+        let buffer = field.bytes().await?;
         assert_eq!(field.length, buffer.len());
-        assert!(field.consumed());
+    }
 
-        tracing::info!("{:#?}", field);
+    Ok(())
+}
+
+#[tokio::test]
+async fn events() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let limit = body.limit();
+
+    let form = FormData::new(body, "------------------------627436eaefdbc285");
+    form.set_max_buf_size(limit)?;
+
+    let mut events = form.events();
+    let mut sequence = Vec::new();
+    let mut buffer = BytesMut::new();
+
+    while let Some(event) = events.try_next().await? {
+        match event {
+            Event::PartStart(meta) => {
+                assert_eq!(meta.length, 0);
+                sequence.push(format!("start {}", meta.name));
+            }
+            Event::Data(buf) => buffer.extend_from_slice(&buf),
+            Event::PartEnd { length } => {
+                assert_eq!(length, buffer.len());
+                buffer.clear();
+                sequence.push(format!("end {length}"));
+            }
+            Event::Complete => sequence.push("complete".into()),
+        }
     }
 
+    assert_eq!(
+        sequence,
+        [
+            "start operations",
+            "end 236",
+            "start map",
+            "end 89",
+            "start 0",
+            "end 21",
+            "start 1",
+            "end 21",
+            "start 2",
+            "end 23",
+            "complete",
+        ]
+    );
+    assert!(events.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncated_length() -> Result<()> {
+    // `--bnd` not after `\r\n` is not a boundary
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(b"x--bndyz"))]);
+    let mut form = FormData::new(body, "bnd");
+
+    assert!(matches!(form.try_next().await, Err(Error::NotMultipart)));
+
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(b"--bndyz"))]);
+    let mut form = FormData::new(body, "bnd");
+
+    assert!(form.try_next().await?.is_none());
+    assert!(form.try_next().await?.is_none());
+
     let state = form.state();
     let state = state
         .try_lock()
         .map_err(|e| Error::TryLockError(e.to_string()))?;
 
     assert!(state.eof());
-    assert_eq!(state.total(), 5);
-    assert_eq!(state.len(), 1027);
+    assert_eq!(state.total(), 0);
+    assert_eq!(state.len(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn field_limits() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
+    let limit = body.limit();
+
+    let mut form = FormData::with_limits(
+        body,
+        "--------------------------434049563556637648550474",
+        Limits::default().file_size(1024).field_size(16),
+    );
+    form.set_max_buf_size(limit)?;
+
+    while let Some(mut field) = form.try_next().await? {
+        let limits = field.limits()?;
+
+        assert_eq!(limits.buffer_size, limit);
+        let max = if field.filename.is_some() {
+            limits.file_size
+        } else {
+            limits.field_size
+        };
+        assert_eq!(max, Some(if field.filename.is_some() { 1024 } else { 16 }));
+
+        field.ignore().await?;
+
+        assert!(matches!(field.limits(), Err(Error::FieldConsumed)));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lines() -> Result<()> {
+    let body = Limited::random_with(File::open("tests/fixtures/sample.txt").await?, 16);
+
+    let mut form = FormData::new(body, "--------------------------434049563556637648550474");
+
+    while let Some(mut field) = form.try_next().await? {
+        let lines = field.lines().try_collect::<Vec<_>>().await?;
+
+        match field.index {
+            2 => {
+                assert_eq!(lines.len(), 11);
+                assert_eq!(lines[0], "{");
+                assert_eq!(lines[1], "  \"compilerOptions\": {");
+                assert_eq!(lines[10], "}");
+            }
+            3 => assert_eq!(lines, ["{", "  \"test\": \"filename\"", "}"]),
+            4 => assert!(lines.is_empty()),
+            _ => assert_eq!(lines, [field.name.as_str()]),
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_context() -> Result<()> {
+    let mapped = Arc::new(AtomicUsize::new(0));
+
+    let body = Limited::random(File::open("tests/fixtures/sample.txt").await?);
+    let limit = body.limit();
+
+    let counter = mapped.clone();
+    let mut form = FormData::with_limits(
+        body,
+        "--------------------------434049563556637648550474",
+        Limits::default().file_size(16),
+    )
+    .with_error_context(move |e| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        e.context("request 42")
+    })?;
+    form.set_max_buf_size(limit)?;
+
+    while let Some(mut field) = form.try_next().await? {
+        if field.filename.is_none() {
+            field.ignore().await?;
+            continue;
+        }
+
+        let err = field.ignore().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "request 42: file is too large, limit to `16`"
+        );
+        assert!(matches!(err, Error::Context(_, e) if matches!(*e, Error::FileTooLarge(16))));
+        break;
+    }
+
+    assert_eq!(mapped.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn save_atomic() -> Result<()> {
+    let dir = tempdir()?;
+
+    let body = Limited::random_with(File::open("tests/fixtures/sample.txt").await?, 16);
+
+    let mut form = FormData::with_limits(
+        body,
+        "--------------------------434049563556637648550474",
+        Limits::default().file_size(100),
+    );
+
+    while let Some(mut field) = form.try_next().await? {
+        let Some(filename) = field.filename.clone() else {
+            field.ignore().await?;
+            continue;
+        };
+        let filepath = dir.path().join(&filename);
+
+        assert_eq!(filename, "tsconfig.json");
+        assert!(matches!(
+            field.save_atomic(&filepath).await,
+            Err(Error::FileTooLarge(100))
+        ));
+        assert!(!filepath.exists());
+        break;
+    }
+
+    let body = Limited::random_with(File::open("tests/fixtures/sample.txt").await?, 16);
+    let mut form = FormData::new(body, "--------------------------434049563556637648550474");
+
+    while let Some(mut field) = form.try_next().await? {
+        let Some(filename) = field.filename.clone() else {
+            field.ignore().await?;
+            continue;
+        };
+        let filepath = dir.path().join(&filename);
+
+        let bytes = field.save_atomic(&filepath).await?;
+        assert_eq!(bytes, field.length as u64);
+        assert_eq!(std::fs::metadata(&filepath)?.len(), bytes);
+    }
+
+    // no temp files are left
+    let mut names = std::fs::read_dir(dir.path())?
+        .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    assert_eq!(names, ["tsconfig.json", "中文.json"]);
+
+    dir.close()?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn builder() -> Result<()> {
+    let builder = Builder::webkit_style()
+        .text("_method", "put")
+        .text("profile[bio]", "hello\r\n\r\n\"quote\"")
+        .file(
+            "media",
+            "a \"b\".txt",
+            &mime::TEXT_PLAIN,
+            "Alpha file content.\r\n",
+        )
+        .text("empty", "");
+
+    let boundary = builder.boundary().to_string();
+    assert!(boundary.starts_with("----WebKitFormBoundary"));
+    assert_eq!(boundary.len(), 38);
+    assert!(boundary[22..].bytes().all(|b| b.is_ascii_alphanumeric()));
+    assert_ne!(boundary, Builder::webkit_style().boundary());
+
+    let body = builder.build();
+    assert!(body.starts_with(format!("--{boundary}\r\n").as_bytes()));
+    assert!(body.ends_with(format!("\r\n--{boundary}--\r\n").as_bytes()));
+
+    let chunks = body
+        .chunks(7)
+        .map(|c| Ok::<_, io::Error>(Bytes::copy_from_slice(c)))
+        .collect::<Vec<_>>();
+    let mut form = FormData::new(stream::iter(chunks), &boundary);
+
+    let mut fields = Vec::new();
+    while let Some(field) = form.try_next().await? {
+        fields.push(field.consume().await?);
+    }
+
+    assert_eq!(fields.len(), 4);
+    assert_eq!(fields[0].0.name, "_method");
+    assert_eq!(fields[0].1, "put");
+    assert_eq!(fields[1].0.name, "profile[bio]");
+    assert_eq!(fields[1].1, "hello\r\n\r\n\"quote\"");
+    assert_eq!(fields[2].0.name, "media");
+    assert_eq!(fields[2].0.filename.as_deref(), Some("a %22b%22.txt"));
+    assert_eq!(fields[2].0.content_type, Some(mime::TEXT_PLAIN));
+    assert_eq!(fields[2].1, "Alpha file content.\r\n");
+    assert_eq!(fields[3].0.name, "empty");
+    assert_eq!(fields[3].1, "");
+
+    let builder = Builder::new().boundary_prefix("--").text("a", "b");
+    assert!(builder.boundary().starts_with("--"));
+    assert_eq!(builder.boundary().len(), 18);
+    assert_eq!(
+        Builder::with_boundary("AaB03x").text("a", "b").build(),
+        "--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb\r\n--AaB03x--\r\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn header_body_ratio() -> Result<()> {
+    let mut payload = BytesMut::new();
+    payload.extend_from_slice(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"padded\"\r\nX-Padding: ",
+    );
+    payload.extend_from_slice(&[b'a'; 10 * 1024]);
+    payload.extend_from_slice(b"\r\n\r\n1\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"plain\"\r\n\r\nhello\r\n--AaB03x--\r\n");
+    let payload = payload.freeze();
+
+    let chunks = || {
+        stream::iter(
+            payload
+                .chunks(1024)
+                .map(|c| Ok::<_, io::Error>(Bytes::copy_from_slice(c)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let mut form = FormData::new(chunks(), "AaB03x");
+    while let Some(mut field) = form.try_next().await? {
+        field.ignore().await?;
+    }
+
+    let mut form = FormData::with_limits(
+        chunks(),
+        "AaB03x",
+        Limits::default().max_header_body_ratio(100.0),
+    );
+
+    let mut field = form.try_next().await?.expect("padded");
+    assert_eq!(field.name, "padded");
+    let err = field.ignore().await.unwrap_err();
+    assert!(matches!(err, Error::SuspiciousPart(_)));
+    assert_eq!(
+        err.to_string(),
+        "part is suspicious, header to body ratio is over `100`"
+    );
+    assert!(field.consumed());
+
+    let mut field = form.try_next().await?.expect("plain");
+    assert_eq!(field.name, "plain");
+    assert_eq!(field.bytes().await?, "hello");
+    assert!(form.try_next().await?.is_none());
+
+    let limits = Limits::default().max_header_body_ratio(100.0);
+
+    // skipped part is checked
+    let mut form = FormData::with_limits(chunks(), "AaB03x", limits.clone());
+    let mut field = form.try_next().await?.expect("padded");
+    assert!(matches!(
+        field.skip_to_end().await,
+        Err(Error::SuspiciousPart(_))
+    ));
+    let mut field = form.try_next().await?.expect("plain");
+    assert_eq!(field.bytes().await?, "hello");
+
+    // drained part is checked
+    let mut form = FormData::with_limits(chunks(), "AaB03x", limits.clone()).take(1);
+    let field = form.try_next().await?.expect("padded");
+    drop(field);
+    assert!(matches!(form.drain().await, Err(Error::SuspiciousPart(_))));
+
+    // dropped part is checked
+    let mut form =
+        FormData::with_limits(chunks(), "AaB03x", limits.clone()).read_mode(ReadMode::Buffered)?;
+    let field = form.try_next().await?.expect("padded");
+    drop(field);
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::SuspiciousPart(_))
+    ));
+    let mut field = form.try_next().await?.expect("plain");
+    assert_eq!(field.bytes().await?, "hello");
+
+    // the error context is applied
+    let mapped = Arc::new(AtomicUsize::new(0));
+    let counter = mapped.clone();
+    let mut form = FormData::with_limits(
+        chunks(),
+        "AaB03x",
+        Limits::default().max_header_body_ratio(100.0),
+    )
+    .with_error_context(move |e| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        e.context("request 42")
+    })?;
+
+    let mut field = form.try_next().await?.expect("padded");
+    let err = field.ignore().await.unwrap_err();
+    assert!(matches!(err, Error::Context(_, e) if matches!(*e, Error::SuspiciousPart(_))));
+    assert_eq!(mapped.load(Ordering::SeqCst), 1);
+
+    // the error is recorded with the progress
+    let context = form.last_error_context().expect("context");
+    assert!(
+        matches!(&*context.error, Error::Context(_, e) if matches!(**e, Error::SuspiciousPart(_)))
+    );
+    assert_eq!((context.total, context.fields), (1, 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_multipart() -> Result<()> {
+    let payload = Bytes::from(format!("{{\"data\": \"{}\"}}", "a".repeat(64 * 1024)));
+    let polled = Arc::new(AtomicUsize::new(0));
+
+    let chunks = |polled: Arc<AtomicUsize>| {
+        stream::iter(
+            payload
+                .chunks(1024)
+                .map(Bytes::copy_from_slice)
+                .collect::<Vec<_>>(),
+        )
+        .map(move |c| {
+            polled.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, io::Error>(c)
+        })
+    };
+
+    // fails fast with the default limits, over `buffer_size`
+    let mut form = FormData::new(chunks(polled.clone()), "AaB03x");
+    assert!(matches!(form.try_next().await, Err(Error::NotMultipart)));
+    assert_eq!(polled.swap(0, Ordering::SeqCst), 9);
+
+    // fails at the end of stream, within the limit
+    let mut form = FormData::new(
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"{\"data\": \"a\"}",
+        ))]),
+        "AaB03x",
+    );
+    assert!(matches!(form.try_next().await, Err(Error::NotMultipart)));
+
+    // fails fast
+    let mut form = FormData::with_limits(
+        chunks(polled.clone()),
+        "AaB03x",
+        Limits::default().max_preamble(1024),
+    );
+    assert!(matches!(form.try_next().await, Err(Error::NotMultipart)));
+    assert_eq!(polled.load(Ordering::SeqCst), 2);
+
+    // preamble is allowed within the limit
+    let mut form = FormData::new(
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"preamble\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb\r\n--AaB03x--\r\n",
+        ))]),
+        "AaB03x",
+    );
+    let mut field = form.try_next().await?.expect("a");
+    assert_eq!(field.bytes().await?, "b");
+    assert!(form.try_next().await?.is_none());
+
+    let mut form = FormData::with_limits(
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"preamble\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb\r\n--AaB03x--\r\n",
+        ))]),
+        "AaB03x",
+        Limits::default().max_preamble(1024),
+    );
+    let mut field = form.try_next().await?.expect("a");
+    assert_eq!(field.bytes().await?, "b");
+    assert!(form.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rfc7578_strict() -> Result<()> {
+    let body = |headers: &str| {
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from(format!(
+            "--AaB03x\r\n{headers}\r\n\r\ndata\r\n--AaB03x--\r\n"
+        )))])
+    };
+
+    let cases = [
+        (
+            "Content-Disposition: form-data-x; name=\"a\"",
+            Error::InvalidDispositionType,
+        ),
+        (
+            "Content-Disposition: form-data; name=\"a\"; filename=\"a.txt\"; filename=\"b.txt\"",
+            Error::DuplicateFilename,
+        ),
+        (
+            "Content-Disposition: form-data; name=\"a\"\r\nContent-Transfer-Encoding: base64",
+            Error::InvalidTransferEncoding("base64".into()),
+        ),
+    ];
+
+    for (headers, err) in cases {
+        // lenient
+        let mut form = FormData::new(body(headers), "AaB03x");
+        let mut field = form.try_next().await?.expect("field");
+        assert_eq!(field.name, "a");
+        assert_eq!(field.bytes().await?, "data");
+
+        // strict
+        let mut form = FormData::new(body(headers), "AaB03x").rfc7578_strict(true)?;
+        assert_eq!(
+            form.try_next().await.unwrap_err().to_string(),
+            err.to_string()
+        );
+    }
+
+    // name is always required, strict mode reports it precisely
+    let headers = "Content-Disposition: form-data; filename=\"a.txt\"";
+    let mut form = FormData::new(body(headers), "AaB03x");
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::InvalidContentDisposition)
+    ));
+    let mut form = FormData::new(body(headers), "AaB03x").rfc7578_strict(true)?;
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::MissingFieldName)
+    ));
+
+    // conforming part
+    let headers = "Content-Disposition: form-data; name=\"a\"; filename=\"a;b.txt\"\r\nContent-Transfer-Encoding: BINARY";
+    let mut form = FormData::new(body(headers), "AaB03x").rfc7578_strict(true)?;
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.name, "a");
+    assert_eq!(field.bytes().await?, "data");
+    assert!(form.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn on_field_complete() -> Result<()> {
+    let completed = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285")
+        .on_field_complete({
+            let completed = completed.clone();
+            move |meta, length| {
+                completed
+                    .lock()
+                    .unwrap()
+                    .push((meta.index, meta.name.clone(), length));
+            }
+        })?;
+
+    while let Some(mut field) = form.try_next().await? {
+        if field.index % 2 == 0 {
+            field.ignore().await?;
+        } else {
+            field.bytes().await?;
+        }
+        // polls the consumed field again
+        assert!(field.try_next().await?.is_none());
+    }
+
+    assert_eq!(
+        *completed.lock().unwrap(),
+        [
+            (0, "operations".to_string(), 236),
+            (1, "map".to_string(), 89),
+            (2, "0".to_string(), 21),
+            (3, "1".to_string(), 21),
+            (4, "2".to_string(), 23),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_mode() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285")
+        .read_mode(ReadMode::Strict)?;
+
+    let mut field = form.try_next().await?.expect("operations");
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::FieldStillActive)
+    ));
+    assert_eq!(field.bytes().await?.len(), 236);
+    let field = form.try_next().await?.expect("map");
+    assert_eq!(field.name, "map");
+
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285")
+        .read_mode(ReadMode::Buffered)?;
+
+    let mut fields = Vec::new();
+    let mut first = form.try_next().await?.expect("operations");
+    assert_eq!(first.peek(2).await?, "[{");
+    while let Some(field) = form.try_next().await? {
+        fields.push(field);
+    }
+    assert_eq!(fields.len(), 4);
+
+    let mut contents = Vec::new();
+    for field in fields.iter_mut().rev() {
+        contents.push((field.name.clone(), field.bytes().await?));
+        assert!(field.consumed());
+    }
+    assert_eq!(
+        contents,
+        [
+            ("2".to_string(), Bytes::from("Charlie file content.\r\n")),
+            ("1".to_string(), Bytes::from("Bravo file content.\r\n")),
+            ("0".to_string(), Bytes::from("Alpha file content.\r\n")),
+            ("map".to_string(), Bytes::from("{ \"0\": [\"0.variables.file\"], \"1\": [\"1.variables.files.0\"], \"2\": [\"1.variables.files.1\"] }")),
+        ]
+    );
+    assert_eq!(first.bytes().await?.len(), 236);
+    assert_eq!(first.length, 236);
+
+    // limits are applied to the buffered data
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::with_limits(
+        body,
+        "------------------------627436eaefdbc285",
+        Limits::default().field_size(100),
+    )
+    .read_mode(ReadMode::Buffered)?;
+
+    let _field = form.try_next().await?.expect("operations");
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::FieldTooLarge(100))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn content_length_noend() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/content-length-noend.txt").await?);
+    let mut form = FormData::new(body, "X-INSOMNIA-BOUNDARY");
+
+    let mut field = form.try_next().await?.expect("title");
+    assert_eq!(field.bytes().await?, "hello");
+    let mut field = form.try_next().await?.expect("file");
+    assert_eq!(field.filename(), Some("a.txt"));
+    assert_eq!(field.bytes().await?, "Alpha file content.\r\n");
+    assert!(form.try_next().await?.is_none());
+
+    // strict mode requires the closing boundary
+    let body = Limited::random(File::open("tests/fixtures/content-length-noend.txt").await?);
+    let mut form = FormData::new(body, "X-INSOMNIA-BOUNDARY").rfc7578_strict(true)?;
+
+    let mut field = form.try_next().await?.expect("title");
+    assert_eq!(field.bytes().await?, "hello");
+    let mut field = form.try_next().await?.expect("file");
+    assert!(matches!(field.bytes().await, Err(Error::IncompleteStream)));
+
+    // declared length is not satisfied
+    let body = |payload: &'static [u8]| {
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(payload))])
+    };
+    for payload in [
+        &b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\nContent-Length: 8\r\n\r\nshort\r\n"[..],
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\nContent-Length: 2\r\n\r\nlonger\r\n",
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nunsized\r\n",
+    ] {
+        let mut form = FormData::new(body(payload), "AaB03x");
+        let mut field = form.try_next().await?.expect("a");
+        assert!(matches!(field.bytes().await, Err(Error::IncompleteStream)));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn poll_budget() -> Result<()> {
+    let payload =
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nhello\r\n--AaB03x--\r\n";
+    let chunks = || {
+        stream::iter(
+            payload
+                .chunks(4)
+                .map(|c| Ok::<_, io::Error>(Bytes::copy_from_slice(c)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let mut polls = 0;
+    let mut form = FormData::new(chunks(), "AaB03x");
+    let field = future::poll_fn(|cx| {
+        polls += 1;
+        form.poll_next_unpin(cx)
+    })
+    .await
+    .transpose()?;
+    assert_eq!(field.map(|f| f.name), Some("a".into()));
+    assert_eq!(polls, 1);
+
+    let mut polls = 0;
+    let mut form = FormData::with_limits(chunks(), "AaB03x", Limits::default().poll_budget(16));
+    let mut field = future::poll_fn(|cx| {
+        polls += 1;
+        form.poll_next_unpin(cx)
+    })
+    .await
+    .transpose()?
+    .expect("a");
+    assert_eq!(field.name, "a");
+    // reads 16 bytes per poll, until the headers are decoded
+    assert_eq!(polls, 4);
+
+    assert_eq!(field.bytes().await?, "hello");
+    assert!(form.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fully_read() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+
+    while let Some(mut field) = form.try_next().await? {
+        assert!(!field.fully_read());
+
+        match field.index {
+            0 => {
+                field.bytes().await?;
+                assert!(field.fully_read());
+                // nothing is left to discard
+                field.ignore().await?;
+                assert!(field.fully_read());
+            }
+            1 => {
+                field.peek(4).await?;
+                field.ignore().await?;
+                assert!(!field.fully_read());
+            }
+            _ => {
+                field.ignore().await?;
+                assert!(!field.fully_read());
+            }
+        }
+
+        assert!(field.consumed());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bare_cr() -> Result<()> {
+    let mut data = Vec::new();
+    for n in 0..2048u32 {
+        data.extend_from_slice(match n % 6 {
+            0 => &b"\r"[..],
+            1 => b"a\r\r",
+            2 => b"\r\n-",
+            3 => b"\r--AaB03x",
+            4 => b"\r\n--AaB03",
+            _ => b"\r\n--AaB03X\r",
+        });
+    }
+
+    let mut payload = BytesMut::new();
+    payload.extend_from_slice(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\r\n",
+    );
+    payload.extend_from_slice(&data);
+    payload.extend_from_slice(
+        b"\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"empty\"\r\n\r\n\r\n--AaB03x--\r\n",
+    );
+    let payload = payload.freeze();
+
+    // every chunk ends on a lone `\r`
+    let mut chunks = Vec::new();
+    let mut rest = payload.clone();
+    while let Some(n) = rest.iter().skip(1).position(|b| *b == b'\r') {
+        chunks.push(Ok::<_, io::Error>(rest.split_to(n + 2)));
+    }
+    chunks.push(Ok(rest));
+    assert!(chunks.len() > 1000);
+
+    let mut form = FormData::new(stream::iter(chunks), "AaB03x");
+
+    let mut field = form.try_next().await?.expect("file");
+    assert_eq!(field.bytes().await?, data);
+    let mut field = form.try_next().await?.expect("empty");
+    assert_eq!(field.bytes().await?, "");
+    assert!(form.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+#[tokio::test]
+async fn realloc_count() -> Result<()> {
+    let mut payload = BytesMut::new();
+    payload.extend_from_slice(b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n");
+    payload.resize(payload.len() + 256 * 1024, b'a');
+    payload.extend_from_slice(b"\r\n--AaB03x--\r\n");
+    let payload = payload.freeze();
+
+    let count = |limits: Limits| {
+        let payload = payload.clone();
+        async move {
+            let chunks = payload
+                .chunks(16 * 1024)
+                .map(|c| Ok::<_, io::Error>(Bytes::copy_from_slice(c)))
+                .collect::<Vec<_>>();
+            let mut form = FormData::with_limits(stream::iter(chunks), "AaB03x", limits);
+            while let Some(mut field) = form.try_next().await? {
+                assert_eq!(field.bytes().await?.len(), 256 * 1024);
+            }
+
+            let state = form.state();
+            let state = state
+                .try_lock()
+                .map_err(|e| Error::TryLockError(e.to_string()))?;
+            anyhow::Ok(state.realloc_count())
+        }
+    };
+
+    let limits = Limits::default().field_size(512 * 1024);
+    let small = count(limits.clone()).await?;
+    let large = count(limits.buffer_size(512 * 1024)).await?;
+    assert!(large < small);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn held_state_guard() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+
+    let state = form.state();
+    let guard = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+
+    let mut cx = Context::from_waker(noop_waker_ref());
+    let Poll::Ready(Some(Err(err))) = form.poll_next_unpin(&mut cx) else {
+        panic!("state is locked");
+    };
+    assert!(matches!(err, Error::TryLockError(_)));
+    assert!(err.to_string().contains("`FormData::state()`"));
+
+    drop(guard);
+    assert!(form.try_next().await?.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn with_searcher() -> Result<()> {
+    struct Counted(Arc<AtomicUsize>);
+
+    impl BoundarySearcher for Counted {
+        fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            haystack.windows(needle.len()).position(|w| w == needle)
+        }
+    }
+
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285")
+        .with_searcher(Counted(count.clone()))?;
+
+    let mut names = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        field.ignore().await?;
+        names.push(field.name);
+    }
+
+    assert_eq!(names, ["operations", "map", "0", "1", "2"]);
+    assert!(count.load(Ordering::SeqCst) >= 6);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn content_types_for() -> Result<()> {
+    let body = |parts: &[(&str, &str)]| {
+        let mut payload = Vec::new();
+        for (name, content_type) in parts {
+            payload.extend_from_slice(format!("--AaB03x\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{name}.bin\"\r\nContent-Type: {content_type}\r\n\r\ndata\r\n").as_bytes());
+        }
+        payload.extend_from_slice(b"--AaB03x--\r\n");
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from(payload))])
+    };
+    let limits = || {
+        Limits::default()
+            .content_types(&[mime::TEXT_PLAIN])
+            .content_types_for("avatar", &[mime::IMAGE_STAR])
+            .content_types_for("document", &[mime::APPLICATION_PDF])
+    };
+
+    let mut form = FormData::with_limits(
+        body(&[
+            ("avatar", "image/png"),
+            ("document", "application/pdf"),
+            ("notes", "text/plain; charset=utf-8"),
+        ]),
+        "AaB03x",
+        limits(),
+    );
+    let mut names = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        assert_eq!(field.bytes().await?, "data");
+        names.push(field.name);
+    }
+    assert_eq!(names, ["avatar", "document", "notes"]);
+
+    for (name, content_type) in [
+        ("avatar", "application/pdf"),
+        ("document", "image/png"),
+        ("notes", "image/png"),
+    ] {
+        let mut form = FormData::with_limits(body(&[(name, content_type)]), "AaB03x", limits());
+        assert!(matches!(
+            form.try_next().await,
+            Err(Error::ContentTypeNotAllowed { name: n, content_type: c })
+                if n == name && c.essence_str() == content_type
+        ));
+    }
+
+    // allows all without allow-lists
+    let mut form = FormData::new(body(&[("avatar", "application/pdf")]), "AaB03x");
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.bytes().await?, "data");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn skip_to_end() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+
+    while let Some(mut field) = form.try_next().await? {
+        if field.index % 2 == 0 {
+            field.bytes().await?;
+            assert!(!field.skip_to_end().await?);
+            assert!(field.fully_read());
+        } else {
+            field.peek(4).await?;
+            assert!(field.skip_to_end().await?);
+            assert!(!field.fully_read());
+        }
+
+        assert!(field.consumed());
+        assert!(!field.skip_to_end().await?);
+    }
+
+    // the rest data is not read as chunks
+    let builder = Builder::new()
+        .file(
+            "a",
+            "a.bin",
+            &mime::APPLICATION_OCTET_STREAM,
+            vec![b'x'; 64 * 1024],
+        )
+        .text("b", "yz");
+    let payload = builder.build();
+    for mode in [ReadMode::Sequential, ReadMode::Buffered] {
+        let body = stream::iter(
+            payload
+                .chunks(1024)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        );
+        let mut form = FormData::new(body, builder.boundary()).read_mode(mode)?;
+        form.set_max_buf_size(1024)?;
+
+        let mut field = form.try_next().await?.expect("a");
+        field.peek(4).await?;
+        if mode == ReadMode::Buffered {
+            // drains the rest of `a`
+            let mut next = form.try_next().await?.expect("b");
+            assert!(field.skip_to_end().await?);
+            assert_eq!(next.bytes().await?, "yz");
+        } else {
+            assert!(field.skip_to_end().await?);
+            let mut next = form.try_next().await?.expect("b");
+            assert_eq!(next.bytes().await?, "yz");
+        }
+        assert!(field.length < 64 * 1024);
+        assert!(!field.fully_read());
+        assert!(form.try_next().await?.is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn tracing_spans() -> Result<()> {
+    #[derive(Clone, Default)]
+    struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::TRACE)
+        .with_current_span(true)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+    while let Some(mut field) = form.try_next().await? {
+        field.bytes().await?;
+    }
+
+    let logs = captured.0.lock().unwrap();
+    let events = logs
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(serde_json::from_slice::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let parsed = events
+        .iter()
+        .filter(|e| e["fields"]["message"] == "part parsed")
+        .map(|e| {
+            (
+                e["span"]["field"].as_str().unwrap(),
+                e["span"]["index"].as_u64().unwrap(),
+                e["span"]["filename"].as_str(),
+                e["fields"]["content_type"].as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        parsed,
+        [
+            ("operations", 0, None, None),
+            ("map", 1, None, None),
+            ("0", 2, Some("a.txt"), Some("text/plain")),
+            ("1", 3, Some("b.txt"), Some("text/plain")),
+            ("2", 4, Some("c.txt"), Some("text/plain")),
+        ]
+    );
+
+    let bytes = events
+        .iter()
+        .filter(|e| e["fields"]["message"] == "polled bytes" && e["span"]["field"] == "map")
+        .map(|e| e["fields"]["bytes"].as_u64().unwrap())
+        .sum::<u64>();
+    assert_eq!(bytes, 89);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn strip_bom() -> Result<()> {
+    let body = || {
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n\xEF\xBB\xBFvalue\r\n--AaB03x--\r\n",
+        ))])
+    };
+
+    let mut form = FormData::new(body(), "AaB03x");
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.text().await?, "\u{feff}value");
+
+    let mut form = FormData::with_limits(body(), "AaB03x", Limits::default().strip_bom(true));
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.text().await?, "value");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn take() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285").take(1);
+    let state = form.form().state();
+
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.name, "operations");
+    assert_eq!(field.peek(2).await?, "[{");
+    drop(field);
+
+    assert!(form.try_next().await?.is_none());
+    assert_eq!(form.remaining(), 0);
+
+    form.drain().await?;
+
+    let state = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    assert!(state.eof());
+    assert_eq!(state.total(), 5);
+    assert_eq!(state.len(), 1027);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn skip_enforces_limits() -> Result<()> {
+    let payload = || {
+        let mut payload = b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nform\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\r\n".to_vec();
+        payload.resize(payload.len() + 64 * 1024, b'x');
+        payload.extend_from_slice(b"\r\n--AaB03x--\r\n");
+        stream::iter(
+            payload
+                .chunks(256)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+    let limits = || Limits::default().file_size(16 * 1024);
+
+    let mut form = FormData::with_limits(payload(), "AaB03x", limits());
+    form.try_next().await?.expect("field").ignore().await?;
+    let mut field = form.try_next().await?.expect("field");
+    assert!(matches!(
+        field.ignore().await,
+        Err(Error::FileTooLarge(16384))
+    ));
+
+    let form = FormData::with_limits(payload(), "AaB03x", limits()).take(0);
+    assert!(matches!(
+        form.drain().await,
+        Err(Error::FileTooLarge(16384))
+    ));
+
+    let mut form = FormData::with_limits(payload(), "AaB03x", limits()).take(2);
+    form.try_next().await?.expect("field").ignore().await?;
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.peek(4).await?, "xxxx");
+    drop(field);
+    assert!(matches!(
+        form.drain().await,
+        Err(Error::FileTooLarge(16384))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn try_read_buffered() -> Result<()> {
+    let head = b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n";
+    let data = vec![b'a'; 20 * 1024];
+    let mut chunk = head.to_vec();
+    chunk.extend_from_slice(&data);
+    let len = chunk.len();
+    let body = stream::iter(vec![
+        Ok::<_, io::Error>(Bytes::from(chunk)),
+        Ok(Bytes::from_static(b"\r\n--AaB03x--\r\n")),
+    ]);
+
+    let mut form = FormData::new(body, "AaB03x");
+    let state = form.state();
+    let mut field = form.try_next().await?.expect("field");
+
+    let mut buffer = BytesMut::new();
+    while let Some(buf) = field.try_read_buffered()? {
+        assert_eq!(buf.len(), Limits::DEFAULT_BUFFER_SIZE);
+        buffer.extend_from_slice(&buf);
+    }
+    assert_eq!(buffer.len(), 2 * Limits::DEFAULT_BUFFER_SIZE);
+    assert!(field.try_read_buffered()?.is_none());
+    assert_eq!(
+        state
+            .try_lock()
+            .map_err(|e| Error::TryLockError(e.to_string()))?
+            .len(),
+        len as u64
+    );
+
+    while let Some(buf) = field.try_next().await? {
+        buffer.extend_from_slice(&buf);
+    }
+    assert_eq!(buffer, &data[..]);
+    assert!(field.try_read_buffered()?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_chunks() -> Result<()> {
+    let payload = b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nform-data\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nhello world\r\n--AaB03x--\r\n";
+    let chunks = payload
+        .chunks(7)
+        .flat_map(|chunk| [Bytes::new(), Bytes::copy_from_slice(chunk)])
+        .chain([Bytes::new()])
+        .map(Ok::<_, io::Error>)
+        .collect::<Vec<_>>();
+
+    let mut form = FormData::new(stream::iter(chunks), "AaB03x");
+    let mut fields = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        fields.push((field.name.clone(), field.bytes().await?));
+    }
+
+    assert_eq!(
+        fields,
+        [
+            ("name".to_string(), Bytes::from_static(b"form-data")),
+            ("file".to_string(), Bytes::from_static(b"hello world")),
+        ]
+    );
+
+    let state = form.state();
+    let state = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    assert!(state.eof());
+    assert_eq!(state.total(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn collect_text_map() -> Result<()> {
+    let form = |limits: Limits| async move {
+        let body = Limited::random(File::open("tests/fixtures/repeated-names.txt").await?);
+        Ok::<_, io::Error>(FormData::with_limits(body, "AaB03x", limits))
+    };
+
+    let mut form1 = form(Limits::default()).await?;
+    assert!(matches!(
+        form1.collect_text_map().await,
+        Err(Error::UnexpectedFile(name)) if name == "avatar"
+    ));
+
+    let mut form2 = form(Limits::default().allow_files_ignored(true)).await?;
+    let map = form2.collect_text_map().await?;
+    assert_eq!(map.keys().collect::<Vec<_>>(), ["tags", "title"]);
+    assert_eq!(map["tags"], ["rust", "http", "multipart"]);
+    assert_eq!(map["title"], ["form-data"]);
+
+    let mut form3 = form(
+        Limits::default()
+            .allow_files_ignored(true)
+            .max_values_per_name(2),
+    )
+    .await?;
+    assert!(matches!(
+        form3.collect_text_map().await,
+        Err(Error::ValuesTooMany { name, max: 2 }) if name == "tags"
+    ));
+
+    let mut form4 = form(Limits::default().allow_files_ignored(true).fields(3)).await?;
+    assert!(matches!(
+        form4.collect_text_map().await,
+        Err(Error::FieldsTooMany(3))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stream_length_overflow() -> Result<()> {
+    let payload = Bytes::from_static(b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nform-data\r\n--AaB03x--\r\n");
+    let limits = Limits {
+        stream_size: None,
+        ..Limits::default()
+    };
+
+    let form = FormData::with_limits(
+        stream::iter(Vec::<io::Result<Bytes>>::new()),
+        "AaB03x",
+        limits,
+    );
+    let state = form.state();
+    let snapshot = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?
+        .snapshot();
+
+    // resumes from an offset near the max of stream length
+    let mut snapshot = serde_json::to_value(snapshot)?;
+    snapshot["length"] = (u64::MAX - 8).into();
+    let snapshot = serde_json::from_value::<StateSnapshot>(snapshot)?;
+    assert_eq!(snapshot.offset(), u64::MAX - 8);
+
+    let body = stream::iter(vec![Ok::<_, io::Error>(payload)]);
+    let (mut form, _) = FormData::restore(snapshot, body)?;
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::PayloadTooLarge(u64::MAX))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_auto() -> Result<()> {
+    let data = (0..100_000u32)
+        .map(|n| (n % 251).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+    let builder = Builder::new()
+        .file(
+            "file",
+            "data.bin",
+            &mime::APPLICATION_OCTET_STREAM,
+            data.clone(),
+        )
+        .text("name", "form-data");
+    let payload = builder.build();
+
+    for (buffer_size, chunk_size) in [
+        (Limits::DEFAULT_BUFFER_SIZE, 100),
+        (Limits::DEFAULT_BUFFER_SIZE, 32 * 1024),
+        (512 * 1024, 100),
+        (512 * 1024, 32 * 1024),
+    ] {
+        let body = stream::iter(
+            payload
+                .chunks(chunk_size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        );
+        let mut form = FormData::new(body, builder.boundary());
+        form.set_max_buf_size(buffer_size)?;
+
+        let mut field = form.try_next().await?.expect("file");
+        let mut writer = Vec::new();
+        assert_eq!(field.copy_auto(&mut writer).await?, data.len() as u64);
+        assert_eq!(writer, data);
+
+        let mut field = form.try_next().await?.expect("name");
+        let mut writer = Vec::new();
+        assert_eq!(field.copy_auto(&mut writer).await?, 9);
+        assert_eq!(writer, b"form-data");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reserved_names() -> Result<()> {
+    let body = || {
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nform-data\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"__proto__\"\r\n\r\n{}\r\n--AaB03x--\r\n",
+        ))])
+    };
+
+    let mut form = FormData::new(body(), "AaB03x");
+    let map = form.collect_text_map().await?;
+    assert_eq!(map["__proto__"], ["{}"]);
+
+    let limits = Limits::default().reserved_names(&["__proto__", "constructor"]);
+    let mut form = FormData::with_limits(body(), "AaB03x", limits);
+    let mut field = form.try_next().await?.expect("name");
+    assert_eq!(field.text().await?, "form-data");
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::ReservedFieldName(name)) if name == "__proto__"
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_part_headers() -> Result<()> {
+    let body = |n: usize| {
+        let mut payload = b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n".to_vec();
+        for i in 1..n {
+            payload.extend_from_slice(format!("X-Header-{i}: {i}\r\n").as_bytes());
+        }
+        payload.extend_from_slice(b"\r\nform-data\r\n--AaB03x--\r\n");
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from(payload))])
+    };
+    let limits = || Limits::default().max_part_headers(10);
+
+    let mut form = FormData::with_limits(body(10), "AaB03x", limits());
+    let mut field = form.try_next().await?.expect("field");
+    assert_eq!(field.headers.as_ref().map(HeaderMap::len), Some(9));
+    assert_eq!(field.text().await?, "form-data");
+
+    let mut form = FormData::with_limits(body(50), "AaB03x", limits());
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::TooManyPartHeaders { limit: 10 })
+    ));
+
+    let mut form = FormData::new(body(50), "AaB03x");
+    assert!(matches!(form.try_next().await, Err(Error::InvalidHeader)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn byteranges() -> Result<()> {
+    let body = Limited::random(File::open("tests/fixtures/byteranges.txt").await?);
+    let mut form = FormData::new(body, "THIS_STRING_SEPARATES");
+    assert!(matches!(
+        form.try_next().await,
+        Err(Error::InvalidContentDisposition)
+    ));
+
+    let body = Limited::random(File::open("tests/fixtures/byteranges.txt").await?);
+    let mut form = FormData::new(body, "THIS_STRING_SEPARATES").byteranges_mode()?;
+
+    let mut ranges = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        assert_eq!(field.name, "");
+        assert_eq!(field.filename, None);
+        assert_eq!(field.content_type, Some(mime::TEXT_PLAIN));
+
+        let headers = field.headers.clone().expect("headers");
+        assert_eq!(
+            headers.get(http::header::CONTENT_TYPE),
+            Some(&"text/plain".parse()?)
+        );
+        let range = headers
+            .get(http::header::CONTENT_RANGE)
+            .expect("content range")
+            .to_str()?
+            .to_string();
+        ranges.push((range, field.bytes().await?));
+    }
+
+    assert_eq!(
+        ranges,
+        [
+            (
+                "bytes 0-9/26".to_string(),
+                Bytes::from_static(b"abcdefghij")
+            ),
+            ("bytes 20-25/26".to_string(), Bytes::from_static(b"uvwxyz")),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn chunked_upload() -> Result<()> {
+    let data = (0..100_000u32)
+        .map(|n| (n % 251).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+    let builder = Builder::new().file(
+        "file",
+        "data.bin",
+        &mime::APPLICATION_OCTET_STREAM,
+        data.clone(),
+    );
+    let payload = builder.build();
+    let part_size = 16 * 1024;
+
+    for chunk_size in [1000, 16 * 1024, 40_000] {
+        let body = stream::iter(
+            payload
+                .chunks(chunk_size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        );
+        let mut form = FormData::new(body, builder.boundary());
+        form.set_max_buf_size(64 * 1024)?;
+        let mut field = form.try_next().await?.expect("file");
+
+        let parts = Arc::new(Mutex::new(Vec::new()));
+        let n = field
+            .chunked_upload(part_size, |index, chunk| {
+                let parts = parts.clone();
+                async move {
+                    parts
+                        .lock()
+                        .map_err(|e| Error::TryLockError(e.to_string()))?
+                        .push((index, chunk));
+                    Ok(())
+                }
+                .boxed()
+            })
+            .await?;
+        assert_eq!(n, data.len() as u64);
+
+        let parts = parts
+            .lock()
+            .map_err(|e| Error::TryLockError(e.to_string()))?;
+        assert_eq!(parts.len(), 7);
+        let mut uploaded = Vec::new();
+        for (i, (index, chunk)) in parts.iter().enumerate() {
+            assert_eq!(*index, i);
+            assert_eq!(
+                chunk.len(),
+                if i < 6 {
+                    part_size
+                } else {
+                    100_000 - 6 * part_size
+                }
+            );
+            uploaded.extend_from_slice(chunk);
+        }
+        assert_eq!(uploaded, data);
+    }
+
+    // stops at the failed part
+    let mut form = FormData::new(
+        stream::iter(vec![Ok::<_, io::Error>(payload.clone())]),
+        builder.boundary(),
+    );
+    let mut field = form.try_next().await?.expect("file");
+    let res = field
+        .chunked_upload(part_size, |index, _| {
+            async move {
+                if index == 2 {
+                    return Err(Error::InvalidHeader);
+                }
+                Ok(())
+            }
+            .boxed()
+        })
+        .await;
+    assert!(matches!(res, Err(Error::InvalidHeader)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn enforce_content_length() -> Result<()> {
+    let body = || {
+        stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+            b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\nContent-Length: 5\r\n\r\nhello\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"b\"\r\nContent-Length: 5\r\n\r\nhello world\r\n--AaB03x--\r\n",
+        ))])
+    };
+
+    let mut form = FormData::new(body(), "AaB03x");
+    let map = form.collect_text_map().await?;
+    assert_eq!(map["a"], ["hello"]);
+    assert_eq!(map["b"], ["hello world"]);
+
+    let limits = Limits::default().enforce_content_length(true);
+    let mut form = FormData::with_limits(body(), "AaB03x", limits);
+    let mut field = form.try_next().await?.expect("a");
+    assert_eq!(field.content_length(), Some(5));
+    assert_eq!(field.text().await?, "hello");
+
+    let mut field = form.try_next().await?.expect("b");
+    assert_eq!(field.content_length(), Some(5));
+    assert!(matches!(
+        field.bytes().await,
+        Err(Error::LengthMismatch {
+            declared: 5,
+            actual: 11
+        })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_to_file_normalized() -> Result<()> {
+    let body = |data: &[u8], size: usize| {
+        let mut payload = b"--AaB03x\r\nContent-Disposition: form-data; name=\"src\"; filename=\"main.rs\"\r\nContent-Type: text/plain\r\n\r\n".to_vec();
+        payload.extend_from_slice(data);
+        payload.extend_from_slice(b"\r\n--AaB03x--\r\n");
+        stream::iter(
+            payload
+                .chunks(size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let dir = tempdir()?;
+    let cases = [
+        (
+            &b"fn main() {\r\n}\r\n\r\n\r// cr\r"[..],
+            LineEnding::Lf,
+            &b"fn main() {\n}\n\n\r// cr\r"[..],
+        ),
+        (
+            b"fn main() {\n}\r\n\n",
+            LineEnding::Crlf,
+            b"fn main() {\r\n}\r\n\r\n",
+        ),
+    ];
+
+    for (data, mode, expected) in cases {
+        for size in [1, 2, 3, 7, 1024] {
+            let mut form = FormData::new(body(data, size), "AaB03x");
+            let mut field = form.try_next().await?.expect("field");
+
+            let path = dir.path().join("main.rs");
+            let mut file = std::fs::File::create(&path)?;
+            let n = field.copy_to_file_normalized(&mut file, mode).await?;
+
+            assert_eq!(std::fs::read(&path)?, expected);
+            assert_eq!(n, expected.len() as u64);
+            assert_eq!(field.length, data.len());
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn to_request_builder() -> Result<()> {
+    let payload = "--AaB03x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\nContent-Length: 5\r\nX-Trace: 1\r\nX-Trace: 2\r\n\r\nhello\r\n--AaB03x--\r\n";
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from(payload))]);
+    let mut form = FormData::new(body, "AaB03x");
+
+    let mut field = form.try_next().await?.expect("file");
+    let req = field
+        .to_request_builder()
+        .method("PUT")
+        .uri("/upload/a.txt")
+        .body(field.bytes().await?)?;
+
+    assert_eq!(req.method(), http::Method::PUT);
+    assert_eq!(req.headers()[http::header::CONTENT_TYPE], "text/plain");
+    assert_eq!(req.headers()[http::header::CONTENT_LENGTH], "5");
+    assert_eq!(
+        req.headers().get_all("x-trace").iter().collect::<Vec<_>>(),
+        ["1", "2"]
+    );
+    assert!(!req
+        .headers()
+        .contains_key(http::header::CONTENT_DISPOSITION));
+    assert_eq!(req.body(), "hello");
+
+    // without headers
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x--\r\n",
+    ))]);
+    let mut form = FormData::new(body, "AaB03x");
+    let field = form.try_next().await?.expect("a");
+    assert!(field.to_request_builder().body(())?.headers().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn quoted_boundary() -> Result<()> {
+    for (content_type, expected) in [
+        (
+            "multipart/form-data; boundary=\"----WebKitFormBoundary7MA4YWxkTrZu0gW\"",
+            Some("----WebKitFormBoundary7MA4YWxkTrZu0gW"),
+        ),
+        (
+            "multipart/form-data; boundary=----WebKitFormBoundary7MA4YWxkTrZu0gW",
+            Some("----WebKitFormBoundary7MA4YWxkTrZu0gW"),
+        ),
+        (
+            "Multipart/Form-Data; charset=utf-8; Boundary=\"AaB03x\"",
+            Some("AaB03x"),
+        ),
+        ("multipart/byteranges; boundary=\"a=b\"", Some("a=b")),
+        ("multipart/form-data; boundary=\"\"", None),
+        ("multipart/form-data", None),
+        ("text/plain; boundary=AaB03x", None),
+    ] {
+        let found = form_data::find_boundary(content_type.as_bytes());
+        let parsed = content_type
+            .parse::<mime::Mime>()
+            .ok()
+            .and_then(|m| form_data::mime_boundary(&m).map(str::to_string));
+
+        assert_eq!(found, expected.map(str::as_bytes), "{content_type}");
+        assert_eq!(parsed.as_deref(), expected, "{content_type}");
+    }
+
+    let content_type = "multipart/form-data; boundary=\"AaB03x\"";
+    let boundary =
+        std::str::from_utf8(form_data::find_boundary(content_type.as_bytes()).expect("boundary"))?;
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from_static(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x--\r\n",
+    ))]);
+    let mut form = FormData::new(body, boundary);
+    let mut field = form.try_next().await?.expect("a");
+    assert_eq!(field.bytes().await?, "xy");
+    assert!(form.try_next().await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn map_chunks() -> Result<()> {
+    let body = |size: usize| {
+        stream::iter(
+            b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nhello, world\r\n--AaB03x--\r\n"
+                .chunks(size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    for size in [1, 3, 1024] {
+        let mut form = FormData::new(body(size), "AaB03x");
+        let mut field = form.try_next().await?.expect("a");
+
+        let mut buffer = BytesMut::new();
+        let mut chunks = field.map_chunks(|buf| Ok(buf.to_ascii_uppercase().into()));
+        while let Some(buf) = chunks.try_next().await? {
+            buffer.extend_from_slice(&buf);
+        }
+        drop(chunks);
+
+        assert_eq!(buffer, "HELLO, WORLD");
+        assert_eq!(field.length, 12);
+        assert!(form.try_next().await?.is_none());
+    }
+
+    // the limits apply to the original data
+    let mut form = FormData::with_limits(body(1024), "AaB03x", Limits::default().field_size(4));
+    let mut field = form.try_next().await?.expect("a");
+    assert!(matches!(
+        field
+            .map_chunks(|_| Ok(Bytes::new()))
+            .try_collect::<Vec<_>>()
+            .await,
+        Err(Error::FieldTooLarge(4))
+    ));
+
+    // the errors of transform are yielded
+    let mut form = FormData::new(body(1024), "AaB03x");
+    let mut field = form.try_next().await?.expect("a");
+    assert!(matches!(
+        field
+            .map_chunks(|_| Err(Error::InvalidHeader))
+            .try_collect::<Vec<_>>()
+            .await,
+        Err(Error::InvalidHeader)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn last_error_context() -> Result<()> {
+    let payload = format!("--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"b\"; filename=\"b.txt\"\r\n\r\n{}\r\n--AaB03x--\r\n", "z".repeat(64));
+    let body = stream::iter(vec![Ok::<_, io::Error>(Bytes::from(payload))]);
+    let mut form = FormData::with_limits(body, "AaB03x", Limits::default().file_size(16))
+        .with_error_context(|e| e.context("req-1"))?;
+    assert!(form.last_error_context().is_none());
+
+    let mut field = form.try_next().await?.expect("a");
+    assert_eq!(field.bytes().await?, "xy");
+    let mut field = form.try_next().await?.expect("b");
+    assert!(
+        matches!(field.bytes().await, Err(Error::Context(_, e)) if matches!(*e, Error::FileTooLarge(16)))
+    );
+
+    let context = form.last_error_context().expect("context");
+    assert!(
+        matches!(&*context.error, Error::Context(c, e) if c == "req-1" && matches!(**e, Error::FileTooLarge(16)))
+    );
+    assert!(matches!(
+        context,
+        ErrorContext {
+            total: 2,
+            files: 1,
+            fields: 1,
+            length: 208,
+            ..
+        }
+    ));
+    assert_eq!(
+        context.to_string(),
+        "req-1: file is too large, limit to `16`, after 2 parts (1 files, 1 fields) and 208 bytes"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn incomplete_headers() -> Result<()> {
+    let body = |payload: &str, size: usize| {
+        stream::iter(
+            payload
+                .as_bytes()
+                .chunks(size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    for size in [1, 3, 1024] {
+        // the first part
+        let mut form = FormData::new(
+            body("--AaB03x\r\nContent-Disposition: form-da", size),
+            "AaB03x",
+        );
+        assert!(matches!(
+            form.try_next().await,
+            Err(Error::IncompleteHeaders)
+        ));
+
+        // the next part
+        let mut form = FormData::new(
+            body("--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x\r\nContent-Disposition: form-data; na", size),
+            "AaB03x",
+        );
+        let mut field = form.try_next().await?.expect("a");
+        assert_eq!(field.bytes().await?, "xy");
+        assert!(matches!(
+            form.try_next().await,
+            Err(Error::IncompleteHeaders)
+        ));
+
+        // the data is truncated, not the headers
+        let mut form = FormData::new(
+            body(
+                "--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy",
+                size,
+            ),
+            "AaB03x",
+        );
+        let mut field = form.try_next().await?.expect("a");
+        assert!(matches!(field.bytes().await, Err(Error::IncompleteStream)));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_final_part() -> Result<()> {
+    async fn parse(
+        form: &mut FormData<impl futures_util::Stream<Item = Result<Bytes, io::Error>> + Unpin>,
+    ) -> Result<Vec<(String, usize)>, Error> {
+        let mut fields = Vec::new();
+        while let Some(mut field) = form.try_next().await? {
+            field.ignore().await?;
+            fields.push((field.name, field.length));
+        }
+        Ok(fields)
+    }
+
+    let body = |tail: &str, size: usize| {
+        let payload = format!("--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"b\"; filename=\"\"\r\nContent-Type: application/octet-stream\r\n\r\n{tail}");
+        stream::iter(
+            payload
+                .into_bytes()
+                .chunks(size)
+                .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+    let expected = [("a".to_string(), 2), ("b".to_string(), 0)];
+
+    for size in [1, 2, 3, 5, 1024] {
+        // with terminator
+        let mut form = FormData::new(body("\r\n--AaB03x--\r\n", size), "AaB03x");
+        assert_eq!(parse(&mut form).await?, expected);
+
+        // without terminator, handled identically
+        for tail in ["\r\n", ""] {
+            let mut form = FormData::new(body(tail, size), "AaB03x");
+            assert_eq!(parse(&mut form).await?, expected);
+            assert!(form
+                .state()
+                .try_lock()
+                .map_err(|e| Error::TryLockError(e.to_string()))?
+                .eof());
+
+            // strict mode requires the closing boundary
+            let mut form = FormData::new(body(tail, size), "AaB03x").rfc7578_strict(true)?;
+            assert!(matches!(
+                parse(&mut form).await,
+                Err(Error::IncompleteStream)
+            ));
+        }
+
+        // the part is not empty
+        let mut form = FormData::new(body("\r", size), "AaB03x");
+        assert!(matches!(
+            parse(&mut form).await,
+            Err(Error::IncompleteStream)
+        ));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn builder_content_type() -> Result<()> {
+    for builder in [
+        Builder::new(),
+        Builder::webkit_style(),
+        Builder::with_boundary("simple boundary"),
+    ] {
+        let builder = builder.text("title", "hello").file(
+            "file",
+            "a.txt",
+            &mime::TEXT_PLAIN,
+            "Alpha file content.",
+        );
+
+        let req = http::Request::post("/upload")
+            .header(http::header::CONTENT_TYPE, builder.content_type_header()?)
+            .body(builder.build())?;
+
+        let content_type = req.headers()[http::header::CONTENT_TYPE]
+            .to_str()?
+            .parse::<mime::Mime>()?;
+        assert_eq!(content_type.essence_str(), "multipart/form-data");
+        let boundary = content_type
+            .get_param(mime::BOUNDARY)
+            .expect("boundary")
+            .to_string();
+        assert_eq!(boundary, builder.boundary());
+        assert_eq!(
+            builder.content_type(),
+            req.headers()[http::header::CONTENT_TYPE]
+        );
+
+        let body = req.into_body();
+        let mut form = FormData::new(stream::iter(vec![Ok::<_, io::Error>(body)]), &boundary);
+
+        let mut fields = Vec::new();
+        while let Some(field) = form.try_next().await? {
+            fields.push(field.consume().await?);
+        }
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].0.name, "title");
+        assert_eq!(fields[0].1, "hello");
+        assert_eq!(fields[1].0.filename.as_deref(), Some("a.txt"));
+        assert_eq!(fields[1].0.content_type, Some(mime::TEXT_PLAIN));
+        assert_eq!(fields[1].1, "Alpha file content.");
+    }
+
+    assert_eq!(
+        Builder::with_boundary("simple boundary").content_type(),
+        "multipart/form-data; boundary=\"simple boundary\""
+    );
+    assert!(matches!(
+        Builder::with_boundary("invalid\r\nboundary").content_type_header(),
+        Err(Error::InvalidHeader)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_restore() -> Result<()> {
+    let data = (0..20_000u32)
+        .map(|n| (n % 251).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+    let builder = Builder::new()
+        .text("title", "hello")
+        .file("file", "data.bin", &mime::APPLICATION_OCTET_STREAM, data)
+        .text("footer", "bye");
+    let payload = builder.build();
+    let chunks = |payload: Bytes| {
+        stream::iter(
+            (0..payload.len())
+                .step_by(1000)
+                .map(|i| Ok::<_, io::Error>(payload.slice(i..payload.len().min(i + 1000))))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let mut expected = Vec::new();
+    let mut range = None;
+    let mut form = FormData::new(chunks(payload.clone()), builder.boundary());
+    while let Some(mut field) = form.try_next().await? {
+        let data = field.bytes().await?;
+        if field.index == 1 {
+            range = field.body_range();
+        }
+        expected.push((field.meta(), data));
+    }
+
+    // snapshots in the middle of the file part
+    let mut form = FormData::new(chunks(payload.clone()), builder.boundary());
+    let mut fields = Vec::new();
+    let field = form.try_next().await?.expect("title");
+    fields.push(field.consume().await?);
+
+    let mut field = form.try_next().await?.expect("file");
+    let mut data = BytesMut::new();
+    data.extend_from_slice(&field.try_next().await?.expect("data"));
+    assert_eq!(field.peek(3).await?.len(), 3);
+    assert!(!field.consumed());
+    let disposition = field.raw_content_disposition().map(<[u8]>::to_vec);
+    assert!(disposition.is_some());
+    let snapshot = serde_json::to_string(&field.snapshot()?)?;
+    drop(field);
+    drop(form);
+
+    // restarts
+    let snapshot = serde_json::from_str::<StateSnapshot>(&snapshot)?;
+    let offset = usize::try_from(snapshot.offset())?;
+    assert!(offset < payload.len());
+    let (mut form, field) = FormData::restore(snapshot, chunks(payload.slice(offset..)))?;
+
+    let mut field = field.expect("file");
+    assert_eq!(field.name, "file");
+    assert_eq!(field.filename.as_deref(), Some("data.bin"));
+    assert_eq!(field.content_type, Some(mime::APPLICATION_OCTET_STREAM));
+    assert_eq!(
+        field.raw_content_disposition().map(<[u8]>::to_vec),
+        disposition
+    );
+    data.extend_from_slice(&field.bytes().await?);
+    assert!(range.is_some());
+    assert_eq!(field.body_range(), range);
+    fields.push((field.meta(), data.freeze()));
+
+    while let Some(field) = form.try_next().await? {
+        fields.push(field.consume().await?);
+    }
+
+    assert_eq!(fields, expected);
+
+    let state = form.state();
+    let state = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    assert!(state.eof());
+    assert_eq!(state.total(), 3);
+    assert_eq!(state.len(), payload.len() as u64 - 2);
 
     Ok(())
 }
@@ -0,0 +1,112 @@
+use std::io::Write;
+
+use anyhow::Result;
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::{Error, FormData, GzipStream, Limits};
+
+fn gzip(data: &[u8], size: usize) -> Result<Vec<Result<Bytes, std::io::Error>>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder
+        .finish()?
+        .chunks(size)
+        .map(|c| Ok(Bytes::copy_from_slice(c)))
+        .collect())
+}
+
+#[tokio::test]
+async fn from_gzip_stream() -> Result<()> {
+    let payload = std::fs::read("tests/fixtures/graphql.txt")?;
+
+    for size in [1, 7, 64, 4096] {
+        let body = stream::iter(gzip(&payload, size)?);
+        let mut form = FormData::from_gzip_stream(body, "------------------------627436eaefdbc285");
+
+        let mut fields = Vec::new();
+        while let Some(mut field) = form.try_next().await? {
+            fields.push((field.name.clone(), field.bytes().await?));
+        }
+
+        assert_eq!(fields.len(), 5);
+        assert_eq!(fields[1].0, "map");
+        assert_eq!(fields[2].1, "Alpha file content.\r\n");
+        assert_eq!(fields[4].1, "Charlie file content.\r\n");
+
+        let state = form.state();
+        let state = state
+            .try_lock()
+            .map_err(|e| Error::TryLockError(e.to_string()))?;
+        assert!(state.eof());
+    }
+
+    // not gzipped
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(payload))]);
+    let mut form = FormData::from_gzip_stream(body, "------------------------627436eaefdbc285");
+    assert!(matches!(form.try_next().await, Err(Error::BoxError(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn gzip_multi_member() -> Result<()> {
+    let payload = std::fs::read("tests/fixtures/graphql.txt")?;
+    let (head, tail) = payload.split_at(payload.len() / 2);
+
+    let mut chunks = gzip(head, 7)?;
+    chunks.extend(gzip(tail, 7)?);
+    let mut form = FormData::from_gzip_stream(
+        stream::iter(chunks),
+        "------------------------627436eaefdbc285",
+    );
+
+    let mut fields = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        fields.push((field.name.clone(), field.bytes().await?));
+    }
+
+    assert_eq!(fields.len(), 5);
+    assert_eq!(fields[4].1, "Charlie file content.\r\n");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn gzip_bomb() -> Result<()> {
+    // a part of 16MB zeros is compressed to a few KB
+    let mut data = b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n".to_vec();
+    data.resize(16 * 1024 * 1024, 0);
+    let compressed = gzip(&data, usize::MAX)?;
+    assert_eq!(compressed.len(), 1);
+
+    // inflated on demand, at most `buffer_size` per poll
+    let mut inflated = GzipStream::new(stream::iter(gzip(&data, usize::MAX)?)).buffer_size(4096);
+    let mut total = 0;
+    while let Some(chunk) = inflated.try_next().await? {
+        assert!(chunk.len() <= 4096);
+        total += chunk.len();
+    }
+    assert_eq!(total, data.len());
+
+    // stops once the inflated data exceeds the limit
+    let mut form = FormData::with_limits(
+        GzipStream::new(stream::iter(compressed)),
+        "AaB03x",
+        Limits::default().stream_size(64 * 1024),
+    );
+    let mut field = form.try_next().await?.expect("a");
+    assert!(matches!(
+        field.bytes().await,
+        Err(Error::PayloadTooLarge(_))
+    ));
+    drop(field);
+    let state = form.state();
+    let state = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    assert!(state.len() <= 64 * 1024 + Limits::DEFAULT_BUFFER_SIZE as u64);
+
+    Ok(())
+}
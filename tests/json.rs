@@ -0,0 +1,227 @@
+use anyhow::Result;
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use serde::Deserialize;
+
+use form_data::{Error, FormData};
+
+const BOUNDARY: &str = "AaB03x";
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Record {
+    id: u32,
+    msg: String,
+}
+
+fn body(data: &[u8], sizes: &[usize]) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"logs\"; filename=\"logs.ndjson\"\r\nContent-Type: application/x-ndjson\r\n\r\n").as_bytes());
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let mut chunks = Vec::new();
+    let mut rest = &payload[..];
+    for size in sizes.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at(rest.len().min(*size));
+        chunks.push(Ok(Bytes::copy_from_slice(chunk)));
+        rest = tail;
+    }
+    chunks
+}
+
+#[tokio::test]
+async fn json_lines() -> Result<()> {
+    let data = b"{\"id\": 1, \"msg\": \"a\"}\r\n{\"id\": 2, \"msg\": \"b\\nc\"}\n\n{\"id\": 3, \"msg\": \"d\"}";
+
+    for sizes in [&[1][..], &[3, 7], &[13, 2, 5], &[1024]] {
+        let mut form = FormData::new(stream::iter(body(data, sizes)), BOUNDARY);
+
+        let mut field = form.try_next().await?.expect("a field");
+        let records = field.json_lines::<Record>().try_collect::<Vec<_>>().await?;
+
+        assert_eq!(
+            records,
+            [
+                Record {
+                    id: 1,
+                    msg: "a".into()
+                },
+                Record {
+                    id: 2,
+                    msg: "b\nc".into()
+                },
+                Record {
+                    id: 3,
+                    msg: "d".into()
+                },
+            ]
+        );
+        assert_eq!(field.length, data.len());
+        assert!(form.try_next().await?.is_none());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_lines_invalid() -> Result<()> {
+    let data = b"{\"id\": 1, \"msg\": \"a\"}\n{\"id\": \n{\"id\": 3, \"msg\": \"d\"}\n";
+
+    let mut form = FormData::new(stream::iter(body(data, &[5])), BOUNDARY);
+    let mut field = form.try_next().await?.expect("a field");
+    let results = field.json_lines::<Record>().collect::<Vec<_>>().await;
+
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::Json(_))));
+    assert_eq!(
+        results[2].as_ref().ok(),
+        Some(&Record {
+            id: 3,
+            msg: "d".into()
+        })
+    );
+
+    let mut form = FormData::new(stream::iter(body(data, &[5])), BOUNDARY);
+    let mut field = form.try_next().await?.expect("a field");
+    let results = field
+        .json_lines::<Record>()
+        .fail_fast(true)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[1], Err(Error::Json(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mode() -> Result<()> {
+    let body = std::fs::read("tests/fixtures/graphql.txt")?;
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(body))]);
+    let mut form =
+        FormData::new(body, "------------------------627436eaefdbc285").graphql_mode()?;
+
+    let mut fields = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        field.ignore().await?;
+        fields.push((field.name, field.content_type));
+    }
+
+    assert_eq!(
+        fields,
+        [
+            ("operations".into(), Some(mime::APPLICATION_JSON)),
+            ("map".into(), Some(mime::APPLICATION_JSON)),
+            ("0".into(), Some(mime::TEXT_PLAIN)),
+            ("1".into(), Some(mime::TEXT_PLAIN)),
+            ("2".into(), Some(mime::TEXT_PLAIN)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mode_missing_file() -> Result<()> {
+    let body = format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{{ \"query\": \"mutation ($file: Upload!) {{ singleUpload(file: $file) {{ id }} }}\", \"variables\": {{ \"file\": null }} }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{{ \"0\": [\"variables.file\"], \"1\": [\"variables.other\"] }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nAlpha file content.\r\n--{BOUNDARY}--\r\n");
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(body))]);
+    let mut form = FormData::new(body, BOUNDARY).graphql_mode()?;
+
+    let mut names = Vec::new();
+    let err = loop {
+        match form.try_next().await {
+            Ok(Some(mut field)) => {
+                field.ignore().await?;
+                names.push(field.name);
+            }
+            Ok(None) => panic!("expected an error"),
+            Err(e) => break e,
+        }
+    };
+
+    assert_eq!(names, ["operations", "map", "0"]);
+    assert!(matches!(err, Error::MissingMappedFile(key) if key == "1"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mode_unmapped_file() -> Result<()> {
+    let body = format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{{ \"query\": \"mutation ($file: Upload!) {{ singleUpload(file: $file) {{ id }} }}\", \"variables\": {{ \"file\": null }} }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{{ \"0\": [\"variables.file\"] }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nAlpha file content.\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"1\"; filename=\"b.txt\"\r\nContent-Type: text/plain\r\n\r\nBravo file content.\r\n--{BOUNDARY}--\r\n");
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(body))]);
+    let mut form = FormData::new(body, BOUNDARY).graphql_mode()?;
+
+    let mut names = Vec::new();
+    let err = loop {
+        match form.try_next().await {
+            Ok(Some(mut field)) => {
+                field.ignore().await?;
+                names.push(field.name);
+            }
+            Ok(None) => panic!("expected an error"),
+            Err(e) => break e,
+        }
+    };
+
+    assert_eq!(names, ["operations", "map", "0"]);
+    assert!(matches!(err, Error::MapMismatch(key) if key == "1"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mode_file_before_map() -> Result<()> {
+    let body = format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{{ \"query\": \"mutation ($file: Upload!) {{ singleUpload(file: $file) {{ id }} }}\", \"variables\": {{ \"file\": null }} }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"1\"; filename=\"b.txt\"\r\nContent-Type: text/plain\r\n\r\nBravo file content.\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{{ \"0\": [\"variables.file\"] }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"0\"; filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\nAlpha file content.\r\n--{BOUNDARY}--\r\n");
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(body))]);
+    let mut form = FormData::new(body, BOUNDARY).graphql_mode()?;
+
+    let mut names = Vec::new();
+    let err = loop {
+        match form.try_next().await {
+            Ok(Some(mut field)) => {
+                if let Err(e) = field.ignore().await {
+                    break e;
+                }
+                names.push(field.name);
+            }
+            Ok(None) => panic!("expected an error"),
+            Err(e) => break e,
+        }
+    };
+
+    // fails once `map` is parsed
+    assert_eq!(names, ["operations", "1"]);
+    assert!(matches!(err, Error::MapMismatch(key) if key == "1"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mode_skipped_map() -> Result<()> {
+    let body = format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{{ \"query\": \"mutation ($file: Upload!) {{ singleUpload(file: $file) {{ id }} }}\", \"variables\": {{ \"file\": null }} }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{{ \"0\": [\"variables.file\"] }}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"1\"; filename=\"b.txt\"\r\nContent-Type: text/plain\r\n\r\nBravo file content.\r\n--{BOUNDARY}--\r\n");
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(body))]);
+    let mut form = FormData::new(body, BOUNDARY).graphql_mode()?;
+
+    let mut names = Vec::new();
+    let err = loop {
+        match form.try_next().await {
+            Ok(Some(mut field)) => {
+                field.skip_to_end().await?;
+                names.push(field.name);
+            }
+            Ok(None) => panic!("expected an error"),
+            Err(e) => break e,
+        }
+    };
+
+    // fails at the end, the skipped `map` is never parsed
+    assert_eq!(names, ["operations", "map", "1"]);
+    assert!(matches!(err, Error::MissingMap));
+
+    Ok(())
+}
@@ -56,7 +56,10 @@ impl Body for IncomingBody {
     }
 
     fn is_end_stream(&self) -> bool {
-        self.0.as_ref().map_or(true, Body::is_end_stream)
+        match &self.0 {
+            Some(body) => body.is_end_stream(),
+            None => true,
+        }
     }
 
     fn size_hint(&self) -> SizeHint {
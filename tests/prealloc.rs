@@ -0,0 +1,70 @@
+#![cfg(target_os = "linux")]
+
+use std::io::{Seek, Write};
+
+use anyhow::Result;
+use async_fs::File;
+use futures_util::stream::TryStreamExt;
+use tempfile::tempdir;
+
+use form_data::FormData;
+
+#[path = "./lib/mod.rs"]
+mod lib;
+
+use lib::Limited;
+
+#[tokio::test]
+async fn copy_to_file_prealloc() -> Result<()> {
+    let dir = tempdir()?;
+
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let limit = body.limit();
+
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+    form.set_max_buf_size(limit)?;
+
+    while let Some(mut field) = form.try_next().await? {
+        let Some(filename) = field.filename.clone() else {
+            field.ignore().await?;
+            continue;
+        };
+
+        let filepath = dir.path().join(filename);
+        let mut file = std::fs::File::create(&filepath)?;
+
+        let hint = match field.index {
+            // larger than data
+            2 => 64 * 1024,
+            // exact
+            3 => 21,
+            // smaller than data
+            _ => 4,
+        };
+
+        let bytes = field.copy_to_file_prealloc(&mut file, hint).await?;
+        assert_eq!(bytes, field.length as u64);
+        assert_eq!(file.metadata()?.len(), bytes);
+        assert_eq!(std::fs::metadata(&filepath)?.len(), bytes);
+    }
+
+    // appends after existing data
+    let body = Limited::random(File::open("tests/fixtures/graphql.txt").await?);
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+
+    let filepath = dir.path().join("all.txt");
+    let mut file = std::fs::File::create(&filepath)?;
+    file.write_all(b"head")?;
+
+    let mut total = 4;
+    while let Some(mut field) = form.try_next().await? {
+        total += field.copy_to_file_prealloc(&mut file, 1024).await?;
+        assert_eq!(file.stream_position()?, total);
+        assert_eq!(file.metadata()?.len(), total);
+    }
+    assert_eq!(total, 4 + 236 + 89 + 21 + 21 + 23);
+
+    dir.close()?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anyhow::Result;
+use bytes::Bytes;
+use futures_util::stream::{self, TryStreamExt};
+
+use form_data::FormData;
+
+const BOUNDARY: &str = "AaB03x";
+
+fn body(parts: &[(&str, &str, &[u8])]) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    for (name, content_type, data) in parts {
+        payload.extend_from_slice(format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{name}.bin\"\r\nContent-Type: {content_type}\r\n\r\n").as_bytes());
+        payload.extend_from_slice(data);
+        payload.extend_from_slice(b"\r\n");
+    }
+    payload.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+    // splits magic numbers across chunks
+    payload
+        .chunks(7)
+        .map(|c| Ok(Bytes::copy_from_slice(c)))
+        .collect()
+}
+
+#[tokio::test]
+async fn verify_content_type() -> Result<()> {
+    let png: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+    let php: &[u8] = b"<?php system($_GET['c']); ?>";
+
+    let mut form = FormData::new(
+        stream::iter(body(&[
+            ("avatar", "image/png", png),
+            ("shell", "image/png", php),
+            ("script", "text/plain", php),
+            ("notes", "text/plain", b"hello"),
+            ("blob", "application/octet-stream", php),
+        ])),
+        BOUNDARY,
+    );
+
+    while let Some(mut field) = form.try_next().await? {
+        let verified = field.verify_content_type().await?;
+
+        // still fully readable
+        let buffer = field.bytes().await?;
+
+        match field.name.as_str() {
+            "avatar" => {
+                assert!(verified);
+                assert_eq!(buffer, png);
+            }
+            "shell" | "script" => {
+                assert!(!verified);
+                assert_eq!(buffer, php);
+            }
+            "notes" => {
+                assert!(verified);
+                assert_eq!(buffer, "hello");
+            }
+            "blob" => {
+                assert!(verified);
+                assert_eq!(buffer, php);
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(field.length, buffer.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::io::Read;
+
+use anyhow::Result;
+use bytes::Bytes;
+use futures_util::stream::{self, TryStreamExt};
+use tempfile::tempdir;
+
+use form_data::{FieldData, FormData, Limits};
+
+const BOUNDARY: &str = "AaB03x";
+
+fn body(fields: &[(&str, &[u8])]) -> Vec<Result<Bytes, std::io::Error>> {
+    let mut payload = Vec::new();
+    for (name, data) in fields {
+        payload.extend_from_slice(
+            format!("--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n")
+                .as_bytes(),
+        );
+        payload.extend_from_slice(data);
+        payload.extend_from_slice(b"\r\n");
+    }
+    payload.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+    payload
+        .chunks(100)
+        .map(|c| Ok(Bytes::copy_from_slice(c)))
+        .collect()
+}
+
+#[tokio::test]
+async fn spool() -> Result<()> {
+    let dir = tempdir()?;
+    let large = vec![b'x'; 4096];
+    let fields = [
+        ("small", &b"hello"[..]),
+        ("exact", &[b'y'; 1024][..]),
+        ("large", &large[..]),
+    ];
+
+    let mut form = FormData::with_limits(
+        stream::iter(body(&fields)),
+        BOUNDARY,
+        Limits::default().spool_threshold(1024),
+    )
+    .with_tempdir(dir.path())?;
+
+    let fields = form.buffered().await?;
+    assert_eq!(fields.len(), 3);
+
+    assert_eq!(fields[0].0.name, "small");
+    assert!(matches!(&fields[0].1, FieldData::Memory(b) if b == "hello"));
+
+    assert_eq!(fields[1].0.name, "exact");
+    assert!(matches!(&fields[1].1, FieldData::Memory(b) if b.len() == 1024));
+
+    assert_eq!(fields[2].0.name, "large");
+    assert_eq!(fields[2].0.length, 4096);
+    let FieldData::File(file) = &fields[2].1 else {
+        panic!("large field is not spooled");
+    };
+    assert_eq!(file.path().parent(), Some(dir.path()));
+    let mut contents = Vec::new();
+    file.reopen()?.read_to_end(&mut contents)?;
+    assert_eq!(contents, large);
+
+    // keeps all in memory without threshold
+    let mut form = FormData::new(stream::iter(body(&[("large", &large)])), BOUNDARY);
+    let mut field = form.try_next().await?.expect("large");
+    assert!(matches!(field.spool().await?, FieldData::Memory(b) if b.len() == 4096));
+
+    drop(fields);
+    dir.close()?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn collect() -> Result<()> {
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from(std::fs::read(
+        "tests/fixtures/sample.txt",
+    )?))]);
+    let form = FormData::with_limits(
+        body,
+        "--------------------------434049563556637648550474",
+        Limits::default().spool_threshold(64),
+    )
+    .collect()
+    .await?;
+
+    assert_eq!(form.text("foo"), Some("foo"));
+    assert_eq!(form.texts("bar"), ["bar"]);
+    assert_eq!(form.text("crab"), Some(""));
+    assert_eq!(form.text("file"), None);
+    assert!(form.texts("missing").is_empty());
+
+    // spooled
+    let file = form.file("file").expect("file");
+    assert_eq!(file.meta.filename.as_deref(), Some("tsconfig.json"));
+    assert_eq!(file.meta.length, 233);
+    let FieldData::File(tmp) = &file.data else {
+        panic!("file is not spooled");
+    };
+    assert_eq!(std::fs::metadata(tmp.path())?.len(), 233);
+
+    // in memory
+    let files = form.files("file2");
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].meta.filename.as_deref(), Some("中文.json"));
+    assert!(
+        matches!(&files[0].data, FieldData::Memory(b) if b == "{\r\n  \"test\": \"filename\"\r\n}\r\n")
+    );
+    assert!(form.file("foo").is_none());
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use bytes::BytesMut;
+use futures_util::stream::TryStreamExt;
+
+use form_data::{Chunked, Error, FormData, Limits};
+
+const BOUNDARY: &str = "------------------------627436eaefdbc285";
+
+async fn graphql(body: Chunked, buffer_size: usize) -> Result<()> {
+    let mut form = FormData::new(body, BOUNDARY);
+    form.set_max_buf_size(buffer_size)?;
+
+    let mut fields = Vec::new();
+    while let Some(mut field) = form.try_next().await? {
+        assert!(!field.consumed());
+        assert_eq!(field.length, 0);
+
+        let mut buffer = BytesMut::new();
+        while let Some(buf) = field.try_next().await? {
+            buffer.extend_from_slice(&buf);
+        }
+
+        assert_eq!(field.length, buffer.len());
+        assert!(field.consumed());
+
+        fields.push((field.name.clone(), field.filename.clone(), buffer.freeze()));
+    }
+
+    assert_eq!(
+        fields
+            .iter()
+            .map(|(name, filename, buffer)| (name.as_str(), filename.as_deref(), buffer.len()))
+            .collect::<Vec<_>>(),
+        [
+            ("operations", None, 236),
+            ("map", None, 89),
+            ("0", Some("a.txt"), 21),
+            ("1", Some("b.txt"), 21),
+            ("2", Some("c.txt"), 23),
+        ]
+    );
+    assert_eq!(fields[1].2, "{ \"0\": [\"0.variables.file\"], \"1\": [\"1.variables.files.0\"], \"2\": [\"1.variables.files.1\"] }");
+    assert_eq!(fields[2].2, "Alpha file content.\r\n");
+    assert_eq!(fields[3].2, "Bravo file content.\r\n");
+    assert_eq!(fields[4].2, "Charlie file content.\r\n");
+
+    let state = form.state();
+    let state = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+
+    assert!(state.eof());
+    assert_eq!(state.total(), 5);
+    assert_eq!(state.len(), 1027);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_chunk_sizes() -> Result<()> {
+    let payload = std::fs::read("tests/fixtures/graphql.txt")?;
+
+    for size in [1, 2, 3, 7, 16, 64, 511, 1023, 1024, 1033] {
+        let body = Chunked::new(payload.clone()).chunk_sizes(vec![size; payload.len() / size + 1]);
+        graphql(body, size).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_straddling_delimiter() -> Result<()> {
+    let payload = std::fs::read("tests/fixtures/graphql.txt")?;
+    let delimiter = format!("\r\n--{BOUNDARY}");
+
+    let positions = payload
+        .windows(delimiter.len())
+        .enumerate()
+        .filter(|(_, w)| *w == delimiter.as_bytes())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(positions.len(), 5);
+
+    // splits at every byte of the delimiters, the trailing `--` and `\r\n`
+    for p in positions {
+        for k in 0..=delimiter.len() + 4 {
+            let body = Chunked::new(payload.clone()).chunk_sizes(vec![p + k]);
+            graphql(body, Limits::DEFAULT_BUFFER_SIZE).await?;
+
+            let body = Chunked::new(payload.clone()).chunk_sizes(vec![p + k, 0, 1]);
+            graphql(body, Limits::DEFAULT_BUFFER_SIZE).await?;
+        }
+    }
+
+    Ok(())
+}
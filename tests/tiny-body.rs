@@ -3,7 +3,11 @@
 //! RUST_LOG=trace cargo test --test tiny-body --no-default-features --features="sync" -- --nocapture
 //! ```
 
-use std::{fs::File, io::Read, str::FromStr};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    str::FromStr,
+};
 
 use anyhow::Result;
 
@@ -121,3 +125,175 @@ fn tiny_body() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn copy_to_writer() -> Result<()> {
+    let builder = Builder::new().text("name", "form-data").file(
+        "file",
+        "a.txt",
+        &mime::TEXT_PLAIN,
+        "hello world\n",
+    );
+    let form = || FormData::new(std::io::Cursor::new(builder.build()), builder.boundary());
+
+    let mut form1 = form();
+    let mut buffer = Vec::new();
+    let mut field = form1.next().transpose()?.expect("name");
+    assert_eq!(field.copy_to(&mut buffer)?, 9);
+    let mut field = form1.next().transpose()?.expect("file");
+    let writer: &mut dyn Write = &mut buffer;
+    assert_eq!(field.copy_to(writer)?, 12);
+    assert_eq!(buffer, b"form-datahello world\n");
+
+    let mut form2 = form();
+    let mut field = form2.next().transpose()?.expect("name");
+    assert_eq!(field.copy_to(&mut std::io::stdout().lock())?, 9);
+
+    Ok(())
+}
+
+#[test]
+fn rate_limit() -> Result<()> {
+    let builder = Builder::new().file(
+        "file",
+        "a.bin",
+        &mime::APPLICATION_OCTET_STREAM,
+        vec![b'x'; 64 * 1024],
+    );
+    let mut form = FormData::new(std::io::Cursor::new(builder.build()), builder.boundary());
+    form.set_max_buf_size(4 * 1024)?;
+    form.set_rate_limit(256 * 1024)?;
+
+    let started = std::time::Instant::now();
+    let mut field = form.next().transpose()?.expect("file");
+    assert_eq!(Field::bytes(&mut field)?.len(), 64 * 1024);
+    assert!(form.next().is_none());
+
+    // the last read is not paced
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= std::time::Duration::from_millis(200),
+        "{elapsed:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fully_read() -> Result<()> {
+    let body = |payload: &'static [u8]| FormData::new(std::io::Cursor::new(payload), "AaB03x");
+
+    let mut form =
+        body(b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x--\r\n");
+    let mut field = form.next().transpose()?.expect("a");
+    assert_eq!(Field::bytes(&mut field)?, "xy");
+    assert!(field.fully_read());
+    field.ignore()?;
+    assert!(field.fully_read());
+
+    // the part is truncated
+    let mut form = body(b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy");
+    let mut field = form.next().transpose()?.expect("a");
+    assert!(matches!(
+        Field::bytes(&mut field),
+        Err(Error::IncompleteStream)
+    ));
+    assert!(!field.fully_read());
+    assert!(!field.consumed());
+
+    Ok(())
+}
+
+#[test]
+fn held_state_guard() -> Result<()> {
+    let builder = Builder::new().text("a", "xy").text("b", "z");
+    let mut form = FormData::new(std::io::Cursor::new(builder.build()), builder.boundary());
+
+    let state = form.state();
+    let guard = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    let err = form
+        .next()
+        .expect("lock error")
+        .expect_err("state is locked");
+    assert!(err.to_string().contains("`FormData::state()`"));
+    drop(guard);
+
+    let mut field = form.next().transpose()?.expect("a");
+    let guard = state
+        .try_lock()
+        .map_err(|e| Error::TryLockError(e.to_string()))?;
+    assert!(matches!(field.next(), Some(Err(Error::TryLockError(_)))));
+    drop(guard);
+
+    assert_eq!(Field::bytes(&mut field)?, "xy");
+
+    Ok(())
+}
+
+#[test]
+fn on_field_complete() -> Result<()> {
+    let completed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let form = |payload: &'static [u8], limits: Limits| {
+        let completed = completed.clone();
+        FormData::with_limits(std::io::Cursor::new(payload), "AaB03x", limits).on_field_complete(
+            move |meta, length| completed.lock().unwrap().push((meta.name.clone(), length)),
+        )
+    };
+
+    let mut form1 = form(b"--AaB03x\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nxy\r\n--AaB03x\r\nContent-Disposition: form-data; name=\"b\"\r\n\r\nz", Limits::default())?;
+    let mut field = form1.next().transpose()?.expect("a");
+    assert_eq!(Field::bytes(&mut field)?, "xy");
+
+    // the part is truncated
+    let mut field = form1.next().transpose()?.expect("b");
+    assert!(matches!(
+        Field::bytes(&mut field),
+        Err(Error::IncompleteStream)
+    ));
+
+    // the part exceeds the limit
+    let mut form2 = form(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"c\"\r\n\r\nxyz\r\n--AaB03x--\r\n",
+        Limits::default().field_size(2),
+    )?;
+    let mut field = form2.next().transpose()?.expect("c");
+    assert!(matches!(
+        Field::bytes(&mut field),
+        Err(Error::FieldTooLarge(2))
+    ));
+
+    assert_eq!(*completed.lock().unwrap(), [("a".to_string(), 2)]);
+
+    Ok(())
+}
+
+#[test]
+fn skip_to_end() -> Result<()> {
+    let builder = Builder::new()
+        .file(
+            "a",
+            "a.bin",
+            &mime::APPLICATION_OCTET_STREAM,
+            vec![b'x'; 64 * 1024],
+        )
+        .text("b", "yz");
+    let mut form = FormData::new(std::io::Cursor::new(builder.build()), builder.boundary());
+    form.set_max_buf_size(1024)?;
+
+    let mut field = form.next().transpose()?.expect("a");
+    field.peek(4)?;
+    assert!(field.skip_to_end()?);
+    assert!(field.length < 64 * 1024);
+    assert!(!field.fully_read());
+    assert!(!field.skip_to_end()?);
+
+    let mut field = form.next().transpose()?.expect("b");
+    assert_eq!(Field::bytes(&mut field)?, "yz");
+    assert!(!field.skip_to_end()?);
+    assert!(field.fully_read());
+    assert!(form.next().is_none());
+
+    Ok(())
+}
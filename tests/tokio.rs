@@ -0,0 +1,261 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use async_fs::File;
+use bytes::{Bytes, BytesMut};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use tempfile::tempdir;
+use tokio::sync::mpsc;
+
+use form_data::{Builder, Error, FormData, Limits};
+
+#[path = "./lib/mod.rs"]
+mod lib;
+
+use lib::Limited;
+
+#[tokio::test]
+async fn send_to() -> Result<()> {
+    let body = Limited::random_with(File::open("tests/fixtures/graphql.txt").await?, 64);
+    let limit = body.limit();
+
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+    form.set_max_buf_size(limit)?;
+
+    while let Some(mut field) = form.try_next().await? {
+        let (tx, mut rx) = mpsc::channel::<Bytes>(1);
+
+        let worker = tokio::spawn(async move {
+            let mut buffer = BytesMut::new();
+            while let Some(buf) = rx.recv().await {
+                buffer.extend_from_slice(&buf);
+            }
+            buffer.freeze()
+        });
+
+        let bytes = field.send_to(tx).await?;
+        let buffer = worker.await?;
+
+        assert_eq!(bytes, buffer.len() as u64);
+        assert_eq!(field.length, buffer.len());
+
+        match field.index {
+            1 => assert_eq!(buffer, "{ \"0\": [\"0.variables.file\"], \"1\": [\"1.variables.files.0\"], \"2\": [\"1.variables.files.1\"] }"),
+            2 => assert_eq!(buffer, "Alpha file content.\r\n"),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn send_to_dropped() -> Result<()> {
+    let body = Limited::new(File::open("tests/fixtures/graphql.txt").await?, 16);
+
+    let mut form = FormData::new(body, "------------------------627436eaefdbc285");
+    form.set_max_buf_size(16)?;
+
+    let mut field = form.try_next().await?.expect("operations");
+    let (tx, mut rx) = mpsc::channel::<Bytes>(1);
+
+    let worker = tokio::spawn(async move { rx.recv().await.map(|buf| buf.len()) });
+
+    let bytes = field.send_to(tx).await?;
+    let received = worker.await?;
+
+    // the channel can buffer one more chunk before the receiver is dropped
+    assert!(received.is_some_and(|n| n > 0 && n as u64 <= bytes));
+    assert!(bytes < 236);
+    assert!(!field.consumed());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn collect_files_to_concurrent() -> Result<()> {
+    let mut builder = Builder::with_boundary("AaB03x").text("title", "files");
+    for n in 0..10 {
+        builder = builder.file(
+            "files",
+            format!("{n}.txt"),
+            &mime::TEXT_PLAIN,
+            format!("file content {n}").repeat(n + 1),
+        );
+    }
+    let builder = builder
+        .file("files", "../escaped.txt", &mime::TEXT_PLAIN, "escaped")
+        .file("files", "0.txt", &mime::TEXT_PLAIN, "duplicate")
+        .file("files", "0.txt", &mime::TEXT_PLAIN, "duplicate".repeat(10));
+    let body = builder.build();
+
+    let dir = tempdir()?;
+    std::fs::write(dir.path().join("escaped.txt"), "existing")?;
+    let chunks = body
+        .chunks(7)
+        .map(|c| Ok::<_, std::io::Error>(Bytes::copy_from_slice(c)))
+        .collect::<Vec<_>>();
+    let mut form = FormData::with_limits(
+        stream::iter(chunks),
+        builder.boundary(),
+        Limits::default().spool_threshold(64),
+    );
+
+    let files = form.collect_files_to_concurrent(dir.path(), 4).await?;
+
+    assert_eq!(files.len(), 13);
+    for (n, (meta, path)) in files.iter().take(10).enumerate() {
+        let expected = format!("file content {n}").repeat(n + 1);
+        assert_eq!(meta.filename.as_deref(), Some(format!("{n}.txt").as_str()));
+        assert_eq!(meta.length, expected.len());
+        assert_eq!(*path, dir.path().join(format!("{n}.txt")));
+        assert_eq!(std::fs::read_to_string(path)?, expected);
+    }
+    // the existing file is kept
+    assert_eq!(files[10].1, dir.path().join("escaped-1.txt"));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("escaped.txt"))?,
+        "existing"
+    );
+    assert_eq!(std::fs::read_to_string(&files[10].1)?, "escaped");
+    assert_eq!(files[11].1, dir.path().join("0-1.txt"));
+    assert_eq!(std::fs::read_to_string(&files[11].1)?, "duplicate");
+    assert_eq!(files[12].1, dir.path().join("0-2.txt"));
+    assert_eq!(
+        std::fs::read_to_string(&files[12].1)?,
+        "duplicate".repeat(10)
+    );
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 14);
+
+    // the created files are removed if it fails
+    let mut builder = Builder::with_boundary("AaB03x");
+    for n in 0..10 {
+        builder = builder.file(
+            "files",
+            format!("{n}.txt"),
+            &mime::TEXT_PLAIN,
+            format!("file content {n}").repeat(n + 1),
+        );
+    }
+    let body = builder.build();
+    let chunks = body
+        .chunks(7)
+        .map(|c| Ok::<_, std::io::Error>(Bytes::copy_from_slice(c)))
+        .collect::<Vec<_>>();
+    let dir = tempdir()?;
+    let mut form = FormData::with_limits(
+        stream::iter(chunks),
+        builder.boundary(),
+        Limits::default().spool_threshold(64).file_size(100),
+    );
+
+    assert!(matches!(
+        form.collect_files_to_concurrent(dir.path(), 4).await,
+        Err(Error::FileTooLarge(100))
+    ));
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn copy_to_tokio() -> Result<()> {
+    let data = (0..20_000u32)
+        .map(|n| (n % 251).to_le_bytes()[0])
+        .collect::<Vec<_>>();
+    let builder = Builder::new().text("name", "form-data").file(
+        "file",
+        "data.bin",
+        &mime::APPLICATION_OCTET_STREAM,
+        data.clone(),
+    );
+    let payload = builder.build();
+    let body = stream::iter(
+        payload
+            .chunks(1000)
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>(),
+    );
+
+    let dir = tempdir()?;
+    let mut form = FormData::new(body, builder.boundary());
+    while let Some(mut field) = form.try_next().await? {
+        let path = dir.path().join(&field.name);
+        let mut file = tokio::fs::File::create(&path).await?;
+        let n = field.copy_to_tokio(&mut file).await?;
+        drop(file);
+
+        let written = tokio::fs::read(&path).await?;
+        assert_eq!(n, written.len() as u64);
+        match field.name.as_str() {
+            "name" => assert_eq!(written, b"form-data"),
+            _ => assert_eq!(written, data),
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn try_next_timeout() -> Result<()> {
+    let body = stream::iter(vec![Ok::<_, std::io::Error>(Bytes::from_static(
+        b"--AaB03x\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nform-data\r\n--AaB03x\r\nContent-Disp",
+    ))])
+    .chain(stream::pending());
+
+    let mut form = FormData::new(body, "AaB03x");
+    let mut field = form
+        .try_next_timeout(Duration::from_secs(1))
+        .await?
+        .expect("field");
+    assert_eq!(field.text().await?, "form-data");
+
+    assert!(matches!(
+        form.try_next_timeout(Duration::from_millis(50)).await,
+        Err(Error::Timeout)
+    ));
+    assert!(matches!(
+        form.try_next_timeout(Duration::from_millis(50)).await,
+        Err(Error::Timeout)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rate_limit() -> Result<()> {
+    let builder = Builder::new().file(
+        "file",
+        "a.bin",
+        &mime::APPLICATION_OCTET_STREAM,
+        vec![b'x'; 64 * 1024],
+    );
+    let payload = builder.build();
+    let body = |payload: &[u8]| {
+        stream::iter(
+            payload
+                .chunks(4 * 1024)
+                .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    let mut form = FormData::new(body(&payload), builder.boundary());
+    form.set_rate_limit(256 * 1024)?;
+
+    let started = tokio::time::Instant::now();
+    let mut field = form.try_next().await?.expect("file");
+    assert_eq!(field.bytes().await?.len(), 64 * 1024);
+    assert!(form.try_next().await?.is_none());
+
+    // the last chunk is not paced
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+
+    // unlimited
+    let mut form = FormData::new(body(&payload), builder.boundary());
+    let mut field = form.try_next().await?.expect("file");
+    assert_eq!(field.bytes().await?.len(), 64 * 1024);
+
+    Ok(())
+}